@@ -0,0 +1,531 @@
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    process,
+};
+
+use durak_rust::{
+    bot::{self, Difficulty},
+    cli::{Options, DEFAULT_NAME},
+    engine::{Action, Engine, Event, Outcome},
+    error::DurakError,
+    external::{ExternalBot, Move, Request, MOVE_TIMEOUT},
+    network::{self, Connection, Message},
+    presets::Presets,
+    replay::Replay,
+    rules::{self, CardId, DeckSize, Rules},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const USAGE: &str = "\
+Usage: durak-term [OPTIONS]
+
+Plays Durak in the terminal against bots.
+    --name NAME          your name
+    --bots N             number of bots to play against
+    --bot NAME           name of the next bot, may be repeated
+    --program COMMAND    program playing the next bot, may be repeated
+    --difficulty LEVEL   easy, normal or hard
+    --seed N             deal and play the bots from this seed, dealing the
+                         same cards as the game window does
+    --preset NAME        rule preset, built-in or saved in the game window
+    --replay FILE        show a saved game move by move with its own players
+                         and rules, then play on from where it stopped; the
+                         last game played is saved as last-game.json next to
+                         the settings of the game window
+    --host ADDRESS       wait at ADDRESS, e.g. 0.0.0.0:7878, for a player to
+                         join, who takes the seat of the first bot
+
+Or join a game someone else is hosting:
+    --join ADDRESS       join the game hosted at ADDRESS under your name
+    -h, --help           print this message";
+
+const HELP: &str = "\
+Cards are written rank then suit, e.g. 10h or 10♥ for the ten of hearts.
+    CARD          attack with the card, or cover the first attack it beats
+    CARD STACK    cover the attack with that number
+    show CARD     show a trump to pass the attack on
+    take, pass    take the table or stop throwing in
+    NUMBER        make the move with that number from the list
+    quit          leave the game";
+
+/// The player at the terminal.
+const YOU: usize = 0;
+
+enum Command<T> {
+    Play(T),
+    Help,
+    Quit,
+}
+
+fn main() {
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(2);
+    });
+    if options.help {
+        println!("{USAGE}");
+        return;
+    }
+    if let Err(error) = options.check_game() {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(2);
+    }
+    if let Err(error) = run(&options) {
+        eprintln!("{error}");
+        process::exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<(), DurakError> {
+    let name = options.name.clone().unwrap_or_else(|| DEFAULT_NAME.into());
+    if let Some(address) = &options.join {
+        return join(address, &name);
+    }
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let (mut replay, mut engine, log) = match &options.replay {
+        Some(path) => {
+            let replay = Replay::load(path)
+                .map_err(|error| DurakError::usage(format!("--replay: {error}")))?;
+            // Checks every move before any is shown.
+            let log = replay.game()?.log().to_vec();
+            let (engine, _) = replay.start()?;
+            (replay, engine, log)
+        }
+        None => {
+            let (replay, engine) = new_game(options, &mut rng)?;
+            (replay, engine, Vec::new())
+        }
+    };
+    let names = &mut replay.players;
+    names[YOU] = name;
+    let difficulty = options.difficulty.unwrap_or(Difficulty::Normal);
+
+    let mut programs: Vec<Option<ExternalBot>> = (0..names.len())
+        .map(|player| {
+            let command = options.programs.get(player.checked_sub(1)?)?;
+            ExternalBot::spawn(command, MOVE_TIMEOUT)
+                .map_err(|error| println!("{}: {error}", names[player]))
+                .ok()
+        })
+        .collect();
+    if let Some(address) = &options.host {
+        println!("Waiting at {address} for a player to join");
+        let (guest, remote) = network::host(address)?;
+        println!("{guest} takes the seat of {}", names[1]);
+        names[1] = guest;
+        programs[1] = Some(remote);
+    }
+
+    println!("Type help for the commands.");
+    for (player, action) in log {
+        println!("{}", describe(&engine, &names[player], action));
+        let events = engine.apply(player, action)?;
+        report(names, &events);
+    }
+    let played = play(&mut engine, names, &mut programs, difficulty, &mut rng);
+    let saved = Replay::record(names.clone(), &engine, replay.deck_size, replay.decks);
+    if let Err(error) = saved.save_last_user() {
+        eprintln!("The game could not be saved: {error}");
+    }
+    played?;
+
+    let Some(outcome) = engine.outcome() else {
+        return Ok(());
+    };
+    let result = match outcome {
+        Outcome::Durak(player) => format!("{} is the durak", names[player]),
+        Outcome::LosingTeam(team) => format!("Team {} lost", team + 1),
+        Outcome::Draw => String::from("It's a draw"),
+    };
+    println!("{result}.");
+    for program in programs.iter_mut().flatten() {
+        program.finish(&result);
+    }
+    Ok(())
+}
+
+/// Deals a game between you and the bots `options` ask for, written down
+/// as a replay with no moves yet. The bots go on playing from `rng`.
+fn new_game(options: &Options, rng: &mut StdRng) -> Result<(Replay, Engine), DurakError> {
+    let rules = match &options.preset {
+        Some(name) => {
+            let presets = Presets::load_user().unwrap_or_else(|error| {
+                eprintln!("{error}, using built-in presets");
+                Presets::default()
+            });
+            presets
+                .get(name)
+                .ok_or_else(|| DurakError::usage(format!("unknown preset {name}")))?
+        }
+        None => Rules::default(),
+    };
+    let bots = options
+        .bots
+        .unwrap_or(options.bot_names.len().max(options.programs.len()).max(1));
+    let max = rules::max_players(DeckSize::Standard, 1, rules.deal_size);
+    if bots == 0 || bots >= max {
+        return Err(DurakError::usage(format!(
+            "between 1 and {} bots can play",
+            max - 1
+        )));
+    }
+    let mut names = vec![String::new()];
+    names.extend((0..bots).map(|bot| {
+        options
+            .bot_names
+            .get(bot)
+            .cloned()
+            .unwrap_or_else(|| format!("Bot {}", bot + 1))
+    }));
+
+    let mut talon = rules::card_ids(DeckSize::Standard, 1);
+    rules::shuffle(&mut talon, rng);
+    let (engine, _) = Engine::new(talon, names.len(), None, rules)?;
+    let replay = Replay::record(names, &engine, DeckSize::Standard, 1);
+    Ok((replay, engine))
+}
+
+/// Plays until the game is over or you quit. Bots played by a program or
+/// by a player who joined fall back on the built-in bot when they fail.
+fn play(
+    engine: &mut Engine,
+    names: &[String],
+    programs: &mut [Option<ExternalBot>],
+    difficulty: Difficulty,
+    rng: &mut StdRng,
+) -> Result<(), DurakError> {
+    let mut lines = io::stdin().lock().lines();
+    while engine.outcome().is_none() {
+        let bot = (0..names.len()).find(|player| *player != YOU && engine.needs_to_act(*player));
+        if let Some(player) = bot {
+            let played = programs[player]
+                .as_mut()
+                .map(|program| program.play(engine, player));
+            let action = match played {
+                Some(Ok(action)) => Some(action),
+                Some(Err(error)) => {
+                    println!("{}: {error}", names[player]);
+                    if programs[player].as_ref().is_some_and(ExternalBot::has_quit) {
+                        programs[player] = None;
+                    }
+                    None
+                }
+                None => None,
+            };
+            let action = action
+                .or_else(|| bot::play(engine, player, difficulty, rng))
+                .ok_or_else(|| DurakError::state("nobody can move"))?;
+            println!("{}", describe(engine, &names[player], action));
+            let events = engine.apply(player, action)?;
+            report(names, &events);
+            continue;
+        }
+        if !engine.needs_to_act(YOU) {
+            return Err(DurakError::state("nobody can move"));
+        }
+
+        let legal = engine.legal_actions(YOU);
+        print_table(engine, names, &legal);
+        print!("> ");
+        io::stdout().flush()?;
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        match parse_command(engine, &legal, line?.trim()) {
+            Ok(Command::Play(action)) => match engine.check(YOU, action) {
+                Ok(()) => {
+                    println!("{}", describe(engine, &names[YOU], action));
+                    let events = engine.apply(YOU, action)?;
+                    report(names, &events);
+                }
+                Err(DurakError::Rules(reason)) => println!("Can't do that: {reason}"),
+                Err(error) => return Err(error),
+            },
+            Ok(Command::Help) => println!("{HELP}"),
+            Ok(Command::Quit) => return Ok(()),
+            Err(message) => println!("{message}"),
+        }
+    }
+    Ok(())
+}
+
+/// Plays a seat of a game hosted elsewhere. The host sends the table
+/// whenever it is your move.
+fn join(address: &str, name: &str) -> Result<(), DurakError> {
+    let mut connection = Connection::join(address, name)?;
+    println!("Joined the game at {address}, waiting for your move.");
+    println!("Type help for the commands.");
+    let mut lines = io::stdin().lock().lines();
+    let mut finished = false;
+    loop {
+        let request = match connection.wait() {
+            Ok(Message::Request(request)) => request,
+            Ok(Message::Finished(finished_game)) => {
+                println!("{}.", finished_game.result);
+                finished = true;
+                continue;
+            }
+            // The host leaves once the game is over.
+            Err(_) if finished => return Ok(()),
+            Err(error) => return Err(error),
+        };
+        finished = false;
+
+        println!();
+        for line in request.summary() {
+            println!("{line}");
+        }
+        let moves: Vec<String> = request
+            .legal
+            .iter()
+            .enumerate()
+            .map(|(i, action)| format!("{}) {}", i + 1, request.label(action)))
+            .collect();
+        println!("Moves: {}", moves.join(", "));
+        loop {
+            print!("> ");
+            io::stdout().flush()?;
+            let Some(line) = lines.next() else {
+                println!();
+                return Ok(());
+            };
+            match parse_reply(&request, line?.trim()) {
+                Ok(Command::Play(action)) => {
+                    connection.reply(request.id, action)?;
+                    break;
+                }
+                Ok(Command::Help) => println!("{HELP}"),
+                Ok(Command::Quit) => return Ok(()),
+                Err(message) => println!("{message}"),
+            }
+        }
+    }
+}
+
+fn cards(cards: &[CardId]) -> String {
+    let cards: Vec<String> = cards.iter().map(CardId::to_string).collect();
+    cards.join(" ")
+}
+
+fn print_table(engine: &Engine, names: &[String], legal: &[Action]) {
+    let bottom = engine.talon().first();
+    println!();
+    println!(
+        "Trump {}{}, {} cards left",
+        engine.trump().symbol(),
+        bottom
+            .map(|card| format!(" ({card} at the bottom)"))
+            .unwrap_or_default(),
+        engine.talon().len()
+    );
+    for (player, name) in names
+        .iter()
+        .enumerate()
+        .filter(|(player, _)| *player != YOU)
+    {
+        let role = if engine.is_out(player) {
+            " (out)"
+        } else if player == engine.defender() {
+            " (defending)"
+        } else if player == engine.attacker() {
+            " (attacking)"
+        } else {
+            ""
+        };
+        println!("{name}: {} cards{role}", engine.hand(player).len());
+    }
+    let stacks: Vec<String> = engine
+        .table()
+        .iter()
+        .enumerate()
+        .map(|(stack, (attack, cover))| match cover {
+            Some(cover) => format!("{}) {attack}/{cover}", stack + 1),
+            None => format!("{}) {attack}", stack + 1),
+        })
+        .collect();
+    println!("Table: {}", stacks.join("  "));
+    println!("Your hand: {}", cards(engine.hand(YOU)));
+    let moves: Vec<String> = legal
+        .iter()
+        .enumerate()
+        .map(|(i, action)| format!("{}) {}", i + 1, label(engine, *action)))
+        .collect();
+    println!("Moves: {}", moves.join(", "));
+}
+
+/// Short name of a move for the list of legal moves.
+fn label(engine: &Engine, action: Action) -> String {
+    match action {
+        Action::Attack(card) => format!("attack {card}"),
+        Action::Defend(stack, card) => format!("cover {} with {card}", engine.table()[stack].0),
+        Action::Transfer(card) => format!("transfer {card}"),
+        Action::ShowTrump(card) => format!("show {card}"),
+        Action::Take => String::from("take"),
+        Action::Pass => String::from("pass"),
+    }
+}
+
+/// What `name` does with `action`, told before it is applied.
+fn describe(engine: &Engine, name: &str, action: Action) -> String {
+    match action {
+        Action::Attack(card) if engine.table().is_empty() => format!("{name} leads {card}"),
+        Action::Attack(card) => format!("{name} throws in {card}"),
+        Action::Defend(stack, card) => {
+            format!("{name} covers {} with {card}", engine.table()[stack].0)
+        }
+        Action::Transfer(card) => format!("{name} transfers with {card}"),
+        Action::ShowTrump(card) => format!("{name} shows {card} and passes the attack on"),
+        Action::Take => format!("{name} will take"),
+        Action::Pass => format!("{name} passes"),
+    }
+}
+
+/// Tells what happened at the end of a bout. Only your own draws are shown.
+fn report(names: &[String], events: &[Event]) {
+    let drawn: Vec<CardId> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Drew(YOU, card) => Some(*card),
+            _ => None,
+        })
+        .collect();
+    for event in events {
+        match event {
+            Event::Took(player) => println!("{} picks up the table", names[*player]),
+            Event::Beaten => println!("Beaten off"),
+            Event::Out(player) => println!("{} is out", names[*player]),
+            _ => {}
+        }
+    }
+    if !drawn.is_empty() {
+        println!("You draw {}", cards(&drawn));
+    }
+}
+
+fn parse_command(engine: &Engine, legal: &[Action], line: &str) -> Result<Command<Action>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let card = |text: &str| {
+        let (rank, suit) =
+            rules::parse_card(text).ok_or_else(|| format!("{text} is not a card, try 10h"))?;
+        engine
+            .hand(YOU)
+            .iter()
+            .find(|card| card.rank == rank && card.suit == suit)
+            .copied()
+            .ok_or_else(|| format!("you don't hold {text}"))
+    };
+    let command = match words.as_slice() {
+        [] => return Err(String::from("Type a move, or help")),
+        ["help" | "?"] => Command::Help,
+        ["quit" | "q"] => Command::Quit,
+        ["take" | "t"] => Command::Play(Action::Take),
+        ["pass" | "p"] => Command::Play(Action::Pass),
+        ["show", text] => Command::Play(Action::ShowTrump(card(text)?)),
+        [number] if number.parse::<usize>().is_ok() => {
+            let index = number.parse::<usize>().unwrap_or_default();
+            let action = index
+                .checked_sub(1)
+                .and_then(|index| legal.get(index))
+                .ok_or_else(|| format!("there is no move {number}"))?;
+            Command::Play(*action)
+        }
+        [text] => Command::Play(engine.play_card(YOU, card(text)?, None)),
+        [text, stack] => {
+            let stack = stack
+                .parse::<usize>()
+                .ok()
+                .and_then(|stack| stack.checked_sub(1))
+                .ok_or_else(|| format!("{stack} is not an attack on the table"))?;
+            Command::Play(engine.play_card(YOU, card(text)?, Some(stack)))
+        }
+        _ => return Err(String::from("Unknown command, type help")),
+    };
+    Ok(command)
+}
+
+/// Reads a command like [`parse_command`] does, for a game hosted elsewhere,
+/// where only the moves the host offers can be made.
+fn parse_reply(request: &Request, line: &str) -> Result<Command<Move>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let card = |text: &str| {
+        let card =
+            rules::parse_card(text).ok_or_else(|| format!("{text} is not a card, try 10h"))?;
+        if request
+            .hand
+            .iter()
+            .any(|held| rules::parse_card(held) == Some(card))
+        {
+            Ok(card)
+        } else {
+            Err(format!("you don't hold {text}"))
+        }
+    };
+    let offered = |wanted: &dyn Fn(&Move) -> bool, what: &str| {
+        request
+            .legal
+            .iter()
+            .filter(|action| wanted(action))
+            // Covering comes before passing the attack on, as in the game.
+            .min_by_key(|action| !matches!(action, Move::Defend { .. }))
+            .cloned()
+            .map(Command::Play)
+            .ok_or_else(|| format!("Can't {what} now"))
+    };
+    let plays = |text: &str| rules::parse_card(text);
+    match words.as_slice() {
+        [] => Err(String::from("Type a move, or help")),
+        ["help" | "?"] => Ok(Command::Help),
+        ["quit" | "q"] => Ok(Command::Quit),
+        ["take" | "t"] => offered(&|action| *action == Move::Take, "take"),
+        ["pass" | "p"] => offered(&|action| *action == Move::Pass, "pass"),
+        ["show", text] => {
+            let shown = card(text)?;
+            offered(
+                &|action| matches!(action, Move::ShowTrump { card } if plays(card) == Some(shown)),
+                &format!("show {text}"),
+            )
+        }
+        [number] if number.parse::<usize>().is_ok() => {
+            let index = number.parse::<usize>().unwrap_or_default();
+            index
+                .checked_sub(1)
+                .and_then(|index| request.legal.get(index))
+                .cloned()
+                .map(Command::Play)
+                .ok_or_else(|| format!("there is no move {number}"))
+        }
+        [text] => {
+            let played = card(text)?;
+            offered(
+                &|action| match action {
+                    Move::Attack { card } | Move::Defend { card, .. } | Move::Transfer { card } => {
+                        plays(card) == Some(played)
+                    }
+                    _ => false,
+                },
+                &format!("play {text}"),
+            )
+        }
+        [text, stack] => {
+            let played = card(text)?;
+            let stack = stack
+                .parse::<usize>()
+                .ok()
+                .and_then(|stack| stack.checked_sub(1))
+                .ok_or_else(|| format!("{stack} is not an attack on the table"))?;
+            offered(
+                &|action| {
+                    matches!(action, Move::Defend { stack: other, card }
+                        if *other == stack && plays(card) == Some(played))
+                },
+                &format!("cover attack {} with {text}", stack + 1),
+            )
+        }
+        _ => Err(String::from("Unknown command, type help")),
+    }
+}
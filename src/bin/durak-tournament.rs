@@ -0,0 +1,324 @@
+use std::{env, process, thread};
+
+use durak_rust::{
+    bot::{self, Difficulty},
+    cli::{Format, Options},
+    engine::{Engine, Outcome},
+    error::DurakError,
+    presets::Presets,
+    rules::{self, DeckSize, Rules},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+
+const USAGE: &str = "\
+Usage: durak-tournament [OPTIONS]
+
+Plays every bot strategy against every other one, head to head, and prints
+win and durak rates with 95% confidence intervals. Games that don't end
+within 10000 moves are counted as stuck, apart from draws.
+    --strategies LIST  bots to play, e.g. easy,normal,hard (the default)
+    --games N          games per pairing, half of them with seats swapped
+                       on the same deal (default 1000)
+    --seed N           seed of the first deal (default 0)
+    --preset NAME      rule preset, built-in or saved in the game window
+    --threads N        games played at once (default: one per core)
+    --format FORMAT    csv (the default) or json
+    -h, --help         print this message";
+
+/// Moves after which a game is given up as stuck. Stuck games are counted
+/// on their own, apart from draws, since they point at a bug.
+const MOVE_LIMIT: usize = 10_000;
+/// Normal quantile of the 95% confidence intervals.
+const Z: f64 = 1.96;
+
+/// What a tournament plays and how it reports.
+struct Tournament {
+    bots: Vec<Difficulty>,
+    games: u64,
+    seed: u64,
+    rules: Rules,
+    threads: usize,
+    format: Format,
+}
+
+impl Tournament {
+    fn new(options: &Options) -> Result<Tournament, DurakError> {
+        options.check_tournament()?;
+        let rules = match &options.preset {
+            Some(name) => {
+                let presets = Presets::load_user().unwrap_or_else(|error| {
+                    eprintln!("{error}, using built-in presets");
+                    Presets::default()
+                });
+                presets
+                    .get(name)
+                    .ok_or_else(|| DurakError::usage(format!("unknown preset {name}")))?
+            }
+            None => Rules::default(),
+        };
+        let bots = match options.strategies.as_slice() {
+            [] => vec![Difficulty::Easy, Difficulty::Normal, Difficulty::Hard],
+            bots => bots.to_vec(),
+        };
+        if bots.len() < 2 {
+            return Err(DurakError::usage("a tournament needs at least two bots"));
+        }
+        if let Some(bot) = bots
+            .iter()
+            .enumerate()
+            .find_map(|(i, bot)| bots[..i].contains(bot).then_some(bot))
+        {
+            return Err(DurakError::usage(format!("{bot:?} is listed twice")));
+        }
+        Ok(Tournament {
+            bots,
+            games: options.games.unwrap_or(1000),
+            seed: options.seed.unwrap_or(0),
+            rules,
+            threads: options
+                .threads
+                .unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from))
+                .max(1),
+            format: options.format.unwrap_or(Format::Csv),
+        })
+    }
+}
+
+/// Results of one strategy against one opponent.
+#[derive(Debug, Clone, Copy, Default)]
+struct Record {
+    games: u64,
+    wins: u64,
+    duraks: u64,
+    draws: u64,
+    stuck: u64,
+}
+
+impl Record {
+    fn add(&mut self, other: Record) {
+        self.games += other.games;
+        self.wins += other.wins;
+        self.duraks += other.duraks;
+        self.draws += other.draws;
+        self.stuck += other.stuck;
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    strategy: String,
+    /// The opponent, or `all` for every game of the strategy.
+    opponent: String,
+    games: u64,
+    wins: u64,
+    duraks: u64,
+    draws: u64,
+    /// Games given up after `MOVE_LIMIT` moves.
+    stuck: u64,
+    win_rate: f64,
+    win_ci: (f64, f64),
+    durak_rate: f64,
+    durak_ci: (f64, f64),
+}
+
+impl Row {
+    fn new(strategy: Difficulty, opponent: String, record: Record) -> Row {
+        let rate = |count: u64| match record.games {
+            0 => 0.,
+            games => count as f64 / games as f64,
+        };
+        Row {
+            strategy: format!("{strategy:?}"),
+            opponent,
+            games: record.games,
+            wins: record.wins,
+            duraks: record.duraks,
+            draws: record.draws,
+            stuck: record.stuck,
+            win_rate: rate(record.wins),
+            win_ci: wilson(record.wins, record.games),
+            durak_rate: rate(record.duraks),
+            durak_ci: wilson(record.duraks, record.games),
+        }
+    }
+}
+
+/// Wilson score interval of a rate at the 95% level.
+fn wilson(successes: u64, trials: u64) -> (f64, f64) {
+    if trials == 0 {
+        return (0., 1.);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let denominator = 1. + Z * Z / n;
+    let centre = (p + Z * Z / (2. * n)) / denominator;
+    let margin = Z * (p * (1. - p) / n + Z * Z / (4. * n * n)).sqrt() / denominator;
+    ((centre - margin).max(0.), (centre + margin).min(1.))
+}
+
+/// How a game of the tournament ended.
+enum Ending {
+    /// The seat of the durak.
+    Durak(usize),
+    Draw,
+    /// Nobody won within `MOVE_LIMIT` moves.
+    Stuck,
+}
+
+/// Plays one game between `bots`, the first one in seat 0. The player with
+/// the lowest trump leads, as in any game.
+fn play_game(bots: [Difficulty; 2], rules: Rules, seed: u64) -> Result<Ending, DurakError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut talon = rules::card_ids(DeckSize::Standard, 1);
+    rules::shuffle(&mut talon, &mut rng);
+    let (mut engine, _) = Engine::new(talon, bots.len(), None, rules)?;
+
+    for _ in 0..MOVE_LIMIT {
+        match engine.outcome() {
+            Some(Outcome::Durak(player)) => return Ok(Ending::Durak(player)),
+            Some(_) => return Ok(Ending::Draw),
+            None => {}
+        }
+        let (player, action) = (0..bots.len())
+            .find_map(|player| {
+                let action = bot::play(&engine, player, bots[player], &mut rng)?;
+                Some((player, action))
+            })
+            .ok_or_else(|| DurakError::state("nobody can move"))?;
+        engine.apply(player, action)?;
+    }
+    Ok(Ending::Stuck)
+}
+
+/// Plays the given games of the pairing of `a` and `b`. Odd games replay
+/// the deal of the game before with the seats swapped.
+fn play_pairing(
+    a: Difficulty,
+    b: Difficulty,
+    tournament: &Tournament,
+    games: std::ops::Range<u64>,
+) -> Result<Record, DurakError> {
+    let mut record = Record::default();
+    for game in games {
+        let seed = tournament.seed.wrapping_add(game / 2);
+        let swapped = game % 2 == 1;
+        let bots = if swapped { [b, a] } else { [a, b] };
+        let ours = usize::from(swapped);
+        record.games += 1;
+        match play_game(bots, tournament.rules, seed)? {
+            Ending::Durak(durak) if durak == ours => record.duraks += 1,
+            Ending::Durak(_) => record.wins += 1,
+            Ending::Draw => record.draws += 1,
+            Ending::Stuck => record.stuck += 1,
+        }
+    }
+    Ok(record)
+}
+
+/// Results of `a` against `b`, played on all threads.
+fn run_pairing(
+    a: Difficulty,
+    b: Difficulty,
+    tournament: &Tournament,
+) -> Result<Record, DurakError> {
+    let chunk = tournament.games.div_ceil(tournament.threads as u64).max(1);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..tournament.games)
+            .step_by(chunk as usize)
+            .map(|first| {
+                let last = (first + chunk).min(tournament.games);
+                scope.spawn(move || play_pairing(a, b, tournament, first..last))
+            })
+            .collect();
+        let mut record = Record::default();
+        for worker in workers {
+            let result = worker
+                .join()
+                .map_err(|_| DurakError::state("a tournament thread panicked"))?;
+            record.add(result?);
+        }
+        Ok(record)
+    })
+}
+
+fn run(tournament: &Tournament) -> Result<Vec<Row>, DurakError> {
+    let bots = &tournament.bots;
+    let mut records = vec![vec![Record::default(); bots.len()]; bots.len()];
+    for a in 0..bots.len() {
+        for b in a + 1..bots.len() {
+            let record = run_pairing(bots[a], bots[b], tournament)?;
+            records[a][b] = record;
+            records[b][a] = Record {
+                wins: record.duraks,
+                duraks: record.wins,
+                ..record
+            };
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (a, strategy) in bots.iter().enumerate() {
+        let mut total = Record::default();
+        for (b, opponent) in bots.iter().enumerate().filter(|(b, _)| *b != a) {
+            total.add(records[a][b]);
+            rows.push(Row::new(*strategy, format!("{opponent:?}"), records[a][b]));
+        }
+        rows.push(Row::new(*strategy, String::from("all"), total));
+    }
+    Ok(rows)
+}
+
+fn print_csv(rows: &[Row]) {
+    println!(
+        "strategy,opponent,games,wins,duraks,draws,stuck,win_rate,win_ci_low,win_ci_high,\
+         durak_rate,durak_ci_low,durak_ci_high"
+    );
+    for row in rows {
+        println!(
+            "{},{},{},{},{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4}",
+            row.strategy,
+            row.opponent,
+            row.games,
+            row.wins,
+            row.duraks,
+            row.draws,
+            row.stuck,
+            row.win_rate,
+            row.win_ci.0,
+            row.win_ci.1,
+            row.durak_rate,
+            row.durak_ci.0,
+            row.durak_ci.1,
+        );
+    }
+}
+
+fn main() {
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(2);
+    });
+    if options.help {
+        println!("{USAGE}");
+        return;
+    }
+    let tournament = Tournament::new(&options).unwrap_or_else(|error| {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(2);
+    });
+    let rows = run(&tournament).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        process::exit(1);
+    });
+    match tournament.format {
+        Format::Csv => print_csv(&rows),
+        Format::Json => match serde_json::to_string_pretty(&rows) {
+            Ok(json) => println!("{json}"),
+            Err(error) => {
+                eprintln!("{error}");
+                process::exit(1);
+            }
+        },
+    }
+}
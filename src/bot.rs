@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::Rank,
+    engine::{Action, Engine},
+    error::DurakError,
+    rules::{self, CardId},
+};
+
+/// How well the bots play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// Any legal move, picked at random.
+    Easy,
+    /// Always the cheapest card that does the job.
+    Normal,
+    /// Like normal, but transfers whenever it can and throws in trumps once
+    /// the deck has run out.
+    Hard,
+}
+
+impl FromStr for Difficulty {
+    type Err = DurakError;
+
+    /// Parses a difficulty by name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(DurakError::usage(format!("unknown difficulty {s}"))),
+        }
+    }
+}
+
+/// Picks a move for `player` playing at `difficulty`, or `None` when they
+/// don't need to act. Random choices come from `rng`.
+pub fn play(
+    engine: &Engine,
+    player: usize,
+    difficulty: Difficulty,
+    rng: &mut impl Rng,
+) -> Option<Action> {
+    if !engine.needs_to_act(player) {
+        return None;
+    }
+    match difficulty {
+        Difficulty::Easy => engine.legal_actions(player).choose(rng).copied(),
+        Difficulty::Normal => choose(engine, player),
+        Difficulty::Hard => {
+            let legal = engine.legal_actions(player);
+            let transfer = legal
+                .iter()
+                .find(|action| matches!(action, Action::Transfer(_) | Action::ShowTrump(_)));
+            let endgame_attack = legal
+                .iter()
+                .filter_map(|action| match action {
+                    Action::Attack(card) if engine.talon().is_empty() => Some(*card),
+                    _ => None,
+                })
+                .min_by_key(|card| value(engine, *card));
+            transfer
+                .copied()
+                .or(endgame_attack.map(Action::Attack))
+                .or_else(|| choose(engine, player))
+        }
+    }
+}
+
+/// How much a card is worth keeping: trumps above everything else, then rank.
+fn value(engine: &Engine, card: CardId) -> (bool, Rank) {
+    (card.suit == engine.trump(), card.rank)
+}
+
+/// Picks a move for `player`, or `None` when they don't need to act.
+///
+/// Plays the cheapest card that does the job, keeps trumps for defending and
+/// takes as soon as one attack can't be covered.
+pub fn choose(engine: &Engine, player: usize) -> Option<Action> {
+    advise(engine, player).map(|(action, _)| action)
+}
+
+/// Like [`choose`], with a short explanation of the move for the player.
+pub fn advise(engine: &Engine, player: usize) -> Option<(Action, &'static str)> {
+    if !engine.needs_to_act(player) {
+        return None;
+    }
+    let hand = engine.hand(player);
+    let is_trump = |card: CardId| card.suit == engine.trump();
+
+    if player == engine.defender() {
+        let mut used = Vec::new();
+        let mut first = None;
+        for (stack, (attack, cover)) in engine.table().iter().enumerate() {
+            if cover.is_some() {
+                continue;
+            }
+            let defence = hand
+                .iter()
+                .filter(|card| !used.contains(*card))
+                .filter(|card| rules::beats(*attack, **card, engine.trump()))
+                .min_by_key(|card| value(engine, **card));
+            match defence {
+                Some(card) => {
+                    used.push(*card);
+                    let reason = if is_trump(*card) && !is_trump(*attack) {
+                        "Only a trump beats this card"
+                    } else {
+                        "Cover with the cheapest card that beats it"
+                    };
+                    first.get_or_insert((Action::Defend(stack, *card), reason));
+                }
+                None => return Some((Action::Take, "You can't beat every card, so take them")),
+            }
+        }
+        return first;
+    }
+
+    let leading = engine.table().is_empty();
+    let legal = engine.legal_actions(player);
+    let attack = legal
+        .iter()
+        .filter_map(|action| match action {
+            Action::Attack(card) if leading || !is_trump(*card) => Some(*card),
+            _ => None,
+        })
+        .min_by_key(|card| value(engine, *card));
+
+    Some(match attack {
+        Some(card) if leading && is_trump(card) => (
+            Action::Attack(card),
+            "Only trumps left, lead the lowest one",
+        ),
+        Some(card) if leading => (
+            Action::Attack(card),
+            "Lead your lowest card and keep the trumps",
+        ),
+        Some(card) => (
+            Action::Attack(card),
+            "Throw in a cheap card of a rank on the table",
+        ),
+        None if legal
+            .iter()
+            .any(|action| matches!(action, Action::Attack(_))) =>
+        {
+            (Action::Pass, "Nothing cheap to throw in, keep your trumps")
+        }
+        None => (Action::Pass, "Nothing to throw in"),
+    })
+}
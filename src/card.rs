@@ -1,15 +1,28 @@
+#[cfg(feature = "gui")]
 use std::hash::Hash;
 
+#[cfg(feature = "gui")]
 use ggez::{
     glam::{vec2, Vec2},
-    graphics::{Canvas, DrawParam, Image, Rect},
+    graphics::{Canvas, Color, DrawParam},
 };
+use serde::{Deserialize, Serialize};
 
-use crate::{cards::Cards, error::DurakError, sprite::Sprite};
+#[cfg(feature = "gui")]
+use crate::{
+    cards::Cards,
+    error::DurakError,
+    rules::{CardId, DeckSize},
+    sprite::Sprite,
+    theme::Theme,
+};
 
+#[cfg(feature = "gui")]
 pub const CARD_WIDTH: f32 = 71.;
+#[cfg(feature = "gui")]
 pub const CARD_HEIGHT: f32 = 96.;
 
+#[cfg(feature = "gui")]
 #[derive(Debug)]
 pub struct Card {
     suit: Suit,
@@ -19,9 +32,13 @@ pub struct Card {
     deck_id: usize,
     position: Vec2,
     rotation: f32,
+    scale: f32,
     show_front: bool,
+    /// Drawn darker, e.g. because it can't be played right now.
+    dimmed: bool,
 }
 
+#[cfg(feature = "gui")]
 impl Card {
     pub fn new(suit: Suit, rank: Rank, front: Sprite, back: Sprite, deck_id: usize) -> Self {
         Card {
@@ -32,7 +49,9 @@ impl Card {
             deck_id,
             position: Vec2::ZERO,
             rotation: 0.,
+            scale: 1.,
             show_front: false,
+            dimmed: false,
         }
     }
 
@@ -51,6 +70,10 @@ impl Card {
         self.show_front = show_face;
     }
 
+    pub fn pos(&self) -> Vec2 {
+        self.position
+    }
+
     pub fn set_pos(&mut self, pos: Vec2) {
         self.position = pos;
     }
@@ -63,6 +86,14 @@ impl Card {
         self.rotation = rotation;
     }
 
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+
+    pub fn set_dimmed(&mut self, dimmed: bool) {
+        self.dimmed = dimmed;
+    }
+
     pub fn suit(&self) -> Suit {
         self.suit
     }
@@ -71,31 +102,52 @@ impl Card {
         self.rank
     }
 
+    pub fn deck_id(&self) -> usize {
+        self.deck_id
+    }
+
+    pub fn id(&self) -> CardId {
+        CardId {
+            suit: self.suit,
+            rank: self.rank,
+            deck_id: self.deck_id,
+        }
+    }
+
     pub fn corners(&self) -> [Vec2; 4] {
         let rotation_vec = Vec2::from_angle(self.rotation);
+        let (width, height) = (CARD_WIDTH * self.scale, CARD_HEIGHT * self.scale);
 
-        let a = self.position - rotation_vec.rotate(vec2(CARD_WIDTH / 2., 0.));
-        let b = a - rotation_vec.rotate(vec2(0., CARD_HEIGHT));
-        let c = b + rotation_vec.rotate(vec2(CARD_WIDTH, 0.));
-        let d = self.position + rotation_vec.rotate(vec2(CARD_WIDTH / 2., 0.));
+        let a = self.position - rotation_vec.rotate(vec2(width / 2., 0.));
+        let b = a - rotation_vec.rotate(vec2(0., height));
+        let c = b + rotation_vec.rotate(vec2(width, 0.));
+        let d = self.position + rotation_vec.rotate(vec2(width / 2., 0.));
 
         [a, b, c, d]
     }
 
     pub fn draw(&self, canvas: &mut Canvas) -> Result<(), DurakError> {
+        let sprite = if self.show_front {
+            &self.front
+        } else {
+            &self.back
+        };
         let card_param = DrawParam::new()
             .dest(self.position)
             .rotation(self.rotation)
-            .offset(vec2(0.5, 1.));
-        if self.show_front {
-            canvas.draw(&self.front, card_param)
+            .offset(vec2(0.5, 1.))
+            .scale(vec2(CARD_WIDTH, CARD_HEIGHT) * self.scale / sprite.size());
+        let card_param = if self.dimmed {
+            card_param.color(Color::from_rgb(120, 120, 120))
         } else {
-            canvas.draw(&self.back, card_param)
-        }
+            card_param
+        };
+        canvas.draw(sprite, card_param);
         Ok(())
     }
 }
 
+#[cfg(feature = "gui")]
 impl Hash for Card {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.suit.hash(state);
@@ -104,17 +156,20 @@ impl Hash for Card {
     }
 }
 
+#[cfg(feature = "gui")]
 impl PartialEq for Card {
     fn eq(&self, other: &Self) -> bool {
-        self.suit == other.suit && self.rank == other.rank
+        self.id() == other.id()
     }
 }
 
+#[cfg(feature = "gui")]
 impl Eq for Card {}
 
+#[cfg(feature = "gui")]
 impl PartialOrd for Card {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.suit != other.suit {
+        if self.suit != other.suit || (self.rank == other.rank && self.deck_id != other.deck_id) {
             None
         } else {
             self.rank.partial_cmp(&other.rank)
@@ -122,7 +177,7 @@ impl PartialOrd for Card {
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum Suit {
     Hearts,
     Diamonds,
@@ -130,8 +185,41 @@ pub enum Suit {
     Clubs,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Hash)]
+impl Suit {
+    /// Suits in the order their rows appear in the classic card sheet.
+    pub const ALL: [Suit; 4] = [Suit::Hearts, Suit::Spades, Suit::Diamonds, Suit::Clubs];
+
+    pub fn symbol(self) -> char {
+        match self {
+            Suit::Hearts => '♥',
+            Suit::Diamonds => '♦',
+            Suit::Spades => '♠',
+            Suit::Clubs => '♣',
+        }
+    }
+
+    pub fn is_red(self) -> bool {
+        matches!(self, Suit::Hearts | Suit::Diamonds)
+    }
+
+    /// Suit for its symbol or initial, e.g. `♥` or `h`.
+    pub fn from_char(c: char) -> Option<Suit> {
+        match c.to_ascii_lowercase() {
+            '♥' | 'h' => Some(Suit::Hearts),
+            '♦' | 'd' => Some(Suit::Diamonds),
+            '♠' | 's' => Some(Suit::Spades),
+            '♣' | 'c' => Some(Suit::Clubs),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum Rank {
+    Two = 2,
+    Three = 3,
+    Four = 4,
+    Five = 5,
     Six = 6,
     Seven = 7,
     Eight = 8,
@@ -143,75 +231,82 @@ pub enum Rank {
     Ace = 14,
 }
 
+impl Rank {
+    /// Short name as printed in the card corner.
+    pub fn label(self) -> &'static str {
+        match self {
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+            Rank::Ace => "A",
+        }
+    }
+
+    /// Rank for its [`label`](Rank::label), ignoring case.
+    pub fn from_label(label: &str) -> Option<Rank> {
+        Rank::ALL
+            .into_iter()
+            .find(|rank| rank.label().eq_ignore_ascii_case(label))
+    }
+
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+}
+
+#[cfg(feature = "gui")]
 #[derive(Debug)]
 pub struct CardFactory {
     decks_made: usize,
-    image: Image,
+    theme: Theme,
 }
 
+#[cfg(feature = "gui")]
 impl CardFactory {
-    pub fn new(image: Image) -> Self {
+    pub fn new(theme: Theme) -> Self {
         CardFactory {
             decks_made: 0,
-            image,
+            theme,
         }
     }
 
-    pub fn get_deck(&mut self) -> Cards {
-        let w = 1. / 9.;
-        let h = 1. / 5.;
-
+    pub fn get_deck(&mut self, size: DeckSize) -> Result<Cards, DurakError> {
         let deck_i = self.decks_made;
-
-        let suit_range = 0..4;
-        let rank_range = 0..9;
-        let back = {
-            let x = w * self.decks_made as f32;
-            let y = h * 4.0;
-            let src = Rect { x, y, w, h };
-            Sprite {
-                src,
-                image: self.image.clone(),
-            }
-        };
+        let back = self.theme.back(deck_i);
 
         self.decks_made += 1;
-        suit_range
-            .flat_map({
-                |i| {
-                    let image = self.image.clone();
-                    let back = back.clone();
-                    rank_range.clone().map(move |j| {
-                        let closure = |j, back| {
-                            let suit = match i {
-                                0 => Suit::Hearts,
-                                1 => Suit::Spades,
-                                2 => Suit::Diamonds,
-                                _ => Suit::Clubs,
-                            };
-                            let rank = match j {
-                                0 => Rank::Six,
-                                1 => Rank::Seven,
-                                2 => Rank::Eight,
-                                3 => Rank::Nine,
-                                4 => Rank::Ten,
-                                5 => Rank::Jack,
-                                6 => Rank::Queen,
-                                7 => Rank::King,
-                                _ => Rank::Ace,
-                            };
-                            let x = w * j as f32;
-                            let y = h * i as f32;
-                            let src = Rect { x, y, w, h };
-                            let front = Sprite {
-                                src,
-                                image: image.clone(),
-                            };
-                            Card::new(suit, rank, front, back, deck_i)
-                        };
-                        closure(j, back.clone())
-                    })
-                }
+        Suit::ALL
+            .into_iter()
+            .flat_map(|suit| size.ranks().map(move |rank| (suit, rank)))
+            .map(|(suit, rank)| {
+                let front = self.theme.front(suit, rank).ok_or_else(|| {
+                    DurakError::asset(format!(
+                        "{:?} of {:?} in theme {}",
+                        rank, suit, self.theme.name
+                    ))
+                })?;
+                Ok(Card::new(suit, rank, front, back.clone(), deck_i))
             })
             .collect()
     }
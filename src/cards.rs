@@ -4,8 +4,6 @@ use std::{
     vec::IntoIter,
 };
 
-
-
 use crate::card::Card;
 
 #[derive(Debug, Default)]
@@ -0,0 +1,277 @@
+use std::{path::PathBuf, str::FromStr};
+
+use crate::{bot::Difficulty, error::DurakError};
+#[cfg(feature = "gui")]
+use crate::{
+    game::DurakState,
+    network,
+    player::{self, Player},
+    replay::Replay,
+    rules,
+    seating::MAX_SEATS,
+};
+
+/// Name of the local player when none was given.
+pub const DEFAULT_NAME: &str = "You";
+
+pub const USAGE: &str = "\
+Usage: durak-rust [OPTIONS]
+
+Any of these options skip the main menu and start a game right away:
+    --name NAME          your name
+    --bots N             number of bots to play against
+    --bot NAME           name of the next bot, may be repeated
+    --program COMMAND    program playing the next bot, may be repeated
+    --difficulty LEVEL   easy, normal or hard
+    --seed N             deal and play the bots from this seed
+    --preset NAME        rule preset to play by
+    --replay FILE        play back a saved game with its own players and
+                         rules, then play on from where it stopped; the
+                         last game played is saved as last-game.json next
+                         to the settings
+    --host ADDRESS       wait at ADDRESS, e.g. 0.0.0.0:7878, for a player to
+                         join, who takes the seat of the first bot
+
+Or join a game someone else is hosting:
+    --join ADDRESS       join the game hosted at ADDRESS under your name
+    -h, --help           print this message";
+
+/// Options given on the command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    pub name: Option<String>,
+    pub bots: Option<usize>,
+    pub bot_names: Vec<String>,
+    /// Command lines of programs playing the bots, in seat order.
+    pub programs: Vec<String>,
+    pub difficulty: Option<Difficulty>,
+    pub seed: Option<u64>,
+    pub preset: Option<String>,
+    /// Saved game to play back.
+    pub replay: Option<PathBuf>,
+    /// Address to wait at for a player to join.
+    pub host: Option<String>,
+    /// Address of a game hosted elsewhere to join.
+    pub join: Option<String>,
+    /// Bot strategies to play against each other in a tournament.
+    pub strategies: Vec<Difficulty>,
+    /// Games per pairing of a tournament.
+    pub games: Option<u64>,
+    /// Tournament games played at once.
+    pub threads: Option<usize>,
+    /// How tournament results are printed.
+    pub format: Option<Format>,
+    pub help: bool,
+}
+
+/// Output format of a tournament.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = DurakError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(DurakError::usage(format!("unknown format {s}"))),
+        }
+    }
+}
+
+impl Options {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, DurakError> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| DurakError::usage(format!("{arg} needs a value")))
+            };
+            match arg.as_str() {
+                "--name" => options.name = Some(value()?),
+                "--bots" => options.bots = Some(parse(&arg, &value()?)?),
+                "--bot" => options.bot_names.push(value()?),
+                "--program" => options.programs.push(value()?),
+                "--difficulty" => options.difficulty = Some(parse(&arg, &value()?)?),
+                "--seed" => options.seed = Some(parse(&arg, &value()?)?),
+                "--preset" => options.preset = Some(value()?),
+                "--replay" => options.replay = Some(PathBuf::from(value()?)),
+                "--host" => options.host = Some(value()?),
+                "--join" => options.join = Some(value()?),
+                "--strategies" => {
+                    options.strategies = value()?
+                        .split(',')
+                        .map(|strategy| parse(&arg, strategy))
+                        .collect::<Result<_, _>>()?
+                }
+                "--games" => options.games = Some(parse(&arg, &value()?)?),
+                "--threads" => options.threads = Some(parse(&arg, &value()?)?),
+                "--format" => options.format = Some(parse(&arg, &value()?)?),
+                "-h" | "--help" => options.help = true,
+                _ => return Err(DurakError::usage(format!("unknown option {arg}"))),
+            }
+        }
+        let others = Options {
+            name: None,
+            ..options.clone()
+        };
+        if options.join.is_some() && others.quick_start() {
+            return Err(DurakError::usage(
+                "--join only goes with --name, the host picks the rest",
+            ));
+        }
+        Ok(options)
+    }
+
+    /// Whether to skip the main menu and start a game.
+    pub fn quick_start(&self) -> bool {
+        self.name.is_some()
+            || self.bots.is_some()
+            || !self.bot_names.is_empty()
+            || !self.programs.is_empty()
+            || self.difficulty.is_some()
+            || self.seed.is_some()
+            || self.preset.is_some()
+            || self.replay.is_some()
+            || self.host.is_some()
+    }
+
+    /// Fails if options only a tournament takes were given to a game.
+    pub fn check_game(&self) -> Result<(), DurakError> {
+        if !self.strategies.is_empty()
+            || self.games.is_some()
+            || self.threads.is_some()
+            || self.format.is_some()
+        {
+            return Err(DurakError::usage(
+                "--strategies, --games, --threads and --format are for durak-tournament",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails if options only a game takes were given to a tournament.
+    pub fn check_tournament(&self) -> Result<(), DurakError> {
+        let game = Options {
+            seed: None,
+            preset: None,
+            ..self.clone()
+        };
+        if game.quick_start() || game.join.is_some() {
+            return Err(DurakError::usage(
+                "a tournament only takes --strategies, --games, --seed, --preset, \
+                 --threads and --format",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sets up the players, bots and rules of the game to start with.
+    #[cfg(feature = "gui")]
+    pub fn apply(&self, state: &mut DurakState) -> Result<(), DurakError> {
+        if let Some(name) = &self.preset {
+            state.rules = state
+                .presets
+                .get(name)
+                .ok_or_else(|| DurakError::usage(format!("unknown preset {name}")))?;
+            state.preset = name.clone();
+        }
+        if let Some(difficulty) = self.difficulty {
+            state.settings.bot_difficulty = difficulty;
+        }
+        state.seed = self.seed;
+
+        let you = &mut state.players[0];
+        if let Some(name) = &self.name {
+            you.name = name.clone();
+        }
+        if you.name.is_empty() && (self.quick_start() || self.join.is_some()) {
+            you.name = String::from(DEFAULT_NAME);
+        }
+
+        if self.bots.is_some() || !self.bot_names.is_empty() || !self.programs.is_empty() {
+            let bots = self
+                .bots
+                .unwrap_or(self.bot_names.len().max(self.programs.len()));
+            let max = rules::max_players(state.deck_size, state.decks, state.rules.deal_size)
+                .min(MAX_SEATS);
+            if bots == 0 || bots >= max {
+                return Err(DurakError::usage(format!(
+                    "between 1 and {} bots can play",
+                    max - 1
+                )));
+            }
+            state.players.truncate(1);
+            for bot in 0..bots {
+                let name = self
+                    .bot_names
+                    .get(bot)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Bot {}", bot + 1));
+                let mut player = Player::new(name, false);
+                player.team = (bot + 1) % 2;
+                player.program = self.programs.get(bot).cloned();
+                state.players.push(player);
+            }
+        }
+
+        if let Some(path) = &self.replay {
+            let replay = Replay::load(path)
+                .map_err(|error| DurakError::usage(format!("--replay: {error}")))?;
+            if replay.players.len() > MAX_SEATS {
+                return Err(DurakError::usage(format!(
+                    "--replay: at most {MAX_SEATS} players fit at the table"
+                )));
+            }
+            state.players = replay
+                .players
+                .iter()
+                .enumerate()
+                .map(|(seat, name)| {
+                    let mut player = Player::new(name.clone(), seat == 0);
+                    player.team = replay
+                        .teams
+                        .as_ref()
+                        .and_then(|teams| teams.get(seat).copied())
+                        .unwrap_or_default();
+                    player
+                })
+                .collect();
+            if let Some(name) = &self.name {
+                state.players[0].name = name.clone();
+            }
+            state.teams = replay.teams.is_some();
+            state.rules = replay.rules;
+            state.deck_size = replay.deck_size;
+            state.decks = replay.decks;
+            state.replay = Some(replay);
+        }
+
+        if let Some(address) = &self.host {
+            let (name, guest) = network::host(address)?;
+            let seat = &mut state.players[1];
+            seat.name = name;
+            seat.program = None;
+            state.guest = Some((1, guest));
+        }
+        state.join = self.join.clone();
+        if let Some(name) = player::shared_name(&state.players) {
+            return Err(DurakError::usage(format!(
+                "two players are called {name}, every player needs a name of their own"
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: FromStr>(option: &str, value: &str) -> Result<T, DurakError> {
+    value
+        .parse()
+        .map_err(|_| DurakError::usage(format!("{option}: invalid value {value}")))
+}
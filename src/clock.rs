@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use crate::{
+    bot,
+    engine::{Action, Engine},
+};
+
+/// Time pressure on the players, chosen in the main menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    Off,
+    /// Time for every single move, reset whenever anyone moves.
+    PerMove(Duration),
+    /// Chess clock: time for the whole game, only running while the player
+    /// has to act.
+    Total(Duration),
+}
+
+/// What happens to a player who runs out of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutAction {
+    /// The defender takes and attackers pass. A player who has to lead
+    /// plays what the bot would.
+    TakeOrPass,
+    /// The bot moves for the player.
+    BotMove,
+}
+
+impl TimeoutAction {
+    pub fn action(self, engine: &Engine, player: usize) -> Option<Action> {
+        match self {
+            TimeoutAction::TakeOrPass => [Action::Take, Action::Pass]
+                .into_iter()
+                .find(|action| engine.check(player, *action).is_ok())
+                .or_else(|| bot::choose(engine, player)),
+            TimeoutAction::BotMove => bot::choose(engine, player),
+        }
+    }
+}
+
+/// Time left for every player.
+#[derive(Debug, Clone)]
+pub struct Clocks {
+    control: TimeControl,
+    remaining: Vec<Duration>,
+    /// Time left of every player after each number of moves, so taking
+    /// moves back gives back their time.
+    history: Vec<Vec<Duration>>,
+}
+
+impl Clocks {
+    pub fn new(control: TimeControl, players: usize) -> Self {
+        let mut clocks = Clocks {
+            control,
+            remaining: vec![Duration::ZERO; players],
+            history: Vec::new(),
+        };
+        clocks.reset();
+        clocks.history.push(clocks.remaining.clone());
+        clocks
+    }
+
+    fn reset(&mut self) {
+        let full = match self.control {
+            TimeControl::Off => Duration::ZERO,
+            TimeControl::PerMove(limit) | TimeControl::Total(limit) => limit,
+        };
+        self.remaining.fill(full);
+    }
+
+    /// Time left for `player`, or `None` without time control.
+    pub fn remaining(&self, player: usize) -> Option<Duration> {
+        (self.control != TimeControl::Off).then(|| self.remaining[player])
+    }
+
+    /// Runs the clocks of the players for whom `running` is true and returns
+    /// those of them who are out of time.
+    pub fn tick(&mut self, delta: Duration, running: impl Fn(usize) -> bool) -> Vec<usize> {
+        if self.control == TimeControl::Off {
+            return Vec::new();
+        }
+        (0..self.remaining.len())
+            .filter(|player| running(*player))
+            .filter(|player| {
+                let remaining = &mut self.remaining[*player];
+                *remaining = remaining.saturating_sub(delta);
+                remaining.is_zero()
+            })
+            .collect()
+    }
+
+    /// Starts a new move. Per-move timers start over, total clocks go on.
+    pub fn moved(&mut self) {
+        if let TimeControl::PerMove(_) = self.control {
+            self.reset();
+        }
+        self.history.push(self.remaining.clone());
+    }
+
+    /// Sets the clocks back to how they were after the first `moves` moves.
+    pub fn rewind(&mut self, moves: usize) {
+        self.history.truncate(moves + 1);
+        if let Some(remaining) = self.history.last() {
+            self.remaining.clone_from(remaining);
+        }
+    }
+}
@@ -1,24 +1,40 @@
 use ggez::{
     glam::vec2,
-    graphics::{DrawParam, Image},
+    graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, Rect, Text, TextAlign, TextLayout},
+    Context,
 };
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use crate::cards::Cards;
 use crate::{
     card::{Card, CardFactory, Suit, CARD_HEIGHT, CARD_WIDTH},
     error::DurakError,
+    rules::{self, CardId, DeckSize},
+    theme::Theme,
 };
 
+/// Where the trump indicator and card counter sit, below the deck.
+const INFO_POS: (f32, f32) = (CARD_WIDTH / 2., CARD_HEIGHT + 24.);
+
 pub struct Deck {
     cards: Cards,
+    /// Trump suit, the suit of the face up bottom card. Kept after that card
+    /// has been drawn.
     kozyr: Suit,
 }
 
 impl Deck {
-    pub fn new(image: &Image) -> Result<Self, DurakError> {
-        let cards = CardFactory::new(image.clone()).get_deck();
-        let kozyr = cards.get(0).unwrap().suit();
+    /// Builds a deck out of `decks` card sets of `size`, each with its own back.
+    pub fn new(theme: &Theme, size: DeckSize, decks: usize) -> Result<Self, DurakError> {
+        let mut factory = CardFactory::new(theme.clone());
+        let mut cards = Cards::new();
+        for _ in 0..decks {
+            cards.append(&mut factory.get_deck(size)?);
+        }
+        let kozyr = cards
+            .get(0)
+            .ok_or_else(|| DurakError::state("empty deck"))?
+            .suit();
 
         Ok(Deck { kozyr, cards })
     }
@@ -27,39 +43,126 @@ impl Deck {
         &self.cards
     }
 
+    pub fn kozyr(&self) -> Suit {
+        self.kozyr
+    }
+
     pub fn pop(&mut self) -> Option<Card> {
         self.cards.pop()
     }
 
-    pub fn shuffle(&mut self) {
-        let len = self.cards.len();
-        for i in 0..len {
-            let r = i + thread_rng().gen_range(0..(len - i));
-            self.cards.swap(i, r);
+    pub fn take(&mut self, id: CardId) -> Option<Card> {
+        let idx = self.cards.iter().position(|card| card.id() == id)?;
+        self.cards.take(idx)
+    }
+
+    pub fn shuffle(&mut self, rng: &mut impl Rng) {
+        rules::shuffle(&mut self.cards[..], rng);
+        self.kozyr = self.cards[0].suit();
+        self.set_card_params();
+    }
+
+    /// Puts the cards in the order of `talon`, e.g. to deal a saved game
+    /// again.
+    pub fn arrange(&mut self, talon: &[CardId]) -> Result<(), DurakError> {
+        let mut cards = self.take_all();
+        let mut arranged = Cards::new();
+        for id in talon {
+            let card = cards
+                .iter()
+                .position(|card| card.id() == *id)
+                .and_then(|idx| cards.take(idx))
+                .ok_or_else(|| DurakError::state(format!("{id} is not in the deck")))?;
+            arranged.push(card);
+        }
+        if cards.len() > 0 {
+            return Err(DurakError::state("the deal leaves cards out of the deck"));
         }
-        self.cards[0].flip(true);
+        self.kozyr = arranged
+            .get(0)
+            .ok_or_else(|| DurakError::state("empty deck"))?
+            .suit();
+        self.restore(arranged);
+        Ok(())
+    }
 
+    /// Takes every card out of the deck, e.g. to lay the table out again.
+    pub fn take_all(&mut self) -> Cards {
+        std::mem::replace(&mut self.cards, Cards::new())
+    }
+
+    /// Puts back `cards`, the last one to be drawn first. The first card is
+    /// the trump and lies face up under the others.
+    pub fn restore(&mut self, cards: Cards) {
+        self.cards = cards;
+        self.set_card_params();
+    }
+
+    fn set_card_params(&mut self) {
         let pos = vec2(CARD_HEIGHT, CARD_WIDTH / 2.);
         let rotation = 270.0_f32.to_radians();
 
+        for card in self.cards.iter_mut() {
+            card.set_scale(1.);
+            card.set_dimmed(false);
+        }
+
         let mut cards_iter = self.cards.iter_mut();
-        let first_card = cards_iter.next().unwrap();
+        let Some(first_card) = cards_iter.next() else {
+            return;
+        };
 
+        first_card.flip(true);
         first_card.set_pos(pos);
         first_card.set_rotation(rotation);
 
         for (i, card) in cards_iter.enumerate() {
-            card.set_pos(vec2((CARD_WIDTH * 7. / 8.) + (2. * i as f32), CARD_HEIGHT))
+            card.flip(false);
+            card.set_pos(vec2((CARD_WIDTH * 7. / 8.) + (2. * i as f32), CARD_HEIGHT));
+            card.set_rotation(0.);
         }
     }
-    pub fn draw(
-        &self,
-        canvas: &mut ggez::graphics::Canvas,
-        _param: impl Into<DrawParam>,
-    ) -> Result<(), DurakError> {
+
+    pub fn draw(&self, canvas: &mut Canvas, ctx: &mut Context) -> Result<(), DurakError> {
         for card in &self.cards {
             card.draw(canvas)?;
         }
+        self.draw_info(canvas, ctx)
+    }
+
+    /// Draws the trump suit on a white badge followed by the number of cards
+    /// left to draw.
+    fn draw_info(&self, canvas: &mut Canvas, ctx: &mut Context) -> Result<(), DurakError> {
+        let pos = vec2(INFO_POS.0, INFO_POS.1);
+        let badge = Mesh::new_rounded_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(-16., -16., 32., 32.),
+            6.,
+            Color::WHITE,
+        )?;
+        canvas.draw(&badge, DrawParam::new().dest(pos));
+
+        let mut suit = Text::new(self.kozyr.symbol());
+        suit.set_font("IBM_CGA")
+            .set_scale(24.)
+            .set_layout(TextLayout::center());
+        let color = if self.kozyr.is_red() {
+            Color::RED
+        } else {
+            Color::BLACK
+        };
+        canvas.draw(&suit, DrawParam::new().dest(pos).color(color));
+
+        let mut count = Text::new(format!("{} left", self.cards.len()));
+        count
+            .set_font("IBM_CGA")
+            .set_scale(16.)
+            .set_layout(TextLayout {
+                h_align: TextAlign::Begin,
+                v_align: TextAlign::Middle,
+            });
+        canvas.draw(&count, DrawParam::new().dest(pos + vec2(24., 0.)));
 
         Ok(())
     }
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use ggez::{
+    glam::{vec2, Vec2},
+    graphics::{Canvas, DrawParam, Text, TextLayout},
+};
+
+use crate::{
+    card::{Card, Suit},
+    cards::Cards,
+    error::DurakError,
+};
+
+/// How far a beaten card travels towards the pile per second.
+const SPEED: f32 = 900.;
+
+/// Face down pile of beaten cards. Cards fly to the pile from wherever they
+/// were when they were discarded.
+#[derive(Debug, Default)]
+pub struct DiscardPile {
+    cards: Cards,
+    pos: Vec2,
+}
+
+impl DiscardPile {
+    pub fn new(pos: Vec2) -> Self {
+        DiscardPile {
+            cards: Cards::new(),
+            pos,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn push(&mut self, mut card: Card) {
+        card.flip(false);
+        card.set_scale(1.);
+        card.set_dimmed(false);
+        self.cards.push(card);
+    }
+
+    pub fn empty(&mut self) {
+        self.cards = Cards::new();
+    }
+
+    /// Discarded cards of `suit`, lowest rank first.
+    pub fn of_suit(&self, suit: Suit) -> Vec<&Card> {
+        let mut cards: Vec<&Card> = self
+            .cards
+            .iter()
+            .filter(|card| card.suit() == suit)
+            .collect();
+        cards.sort_by_key(|card| card.rank());
+        cards
+    }
+
+    pub fn take_all(&mut self) -> Cards {
+        std::mem::replace(&mut self.cards, Cards::new())
+    }
+
+    /// Puts every card in flight straight onto the pile.
+    pub fn settle(&mut self) {
+        self.update(Duration::MAX);
+    }
+
+    /// Moves the cards still in flight towards the pile.
+    pub fn update(&mut self, delta: Duration) {
+        let step = SPEED * delta.as_secs_f32();
+        for (i, card) in self.cards.iter_mut().enumerate() {
+            // Every card lands a little askew so the pile looks messy.
+            let rotation = ((i * 37 % 21) as f32 - 10.).to_radians();
+            let offset = card.pos() - self.pos;
+            if offset.length() <= step {
+                card.set_pos(self.pos);
+                card.set_rotation(rotation);
+            } else {
+                card.move_pos(-offset.normalize() * step);
+            }
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) -> Result<(), DurakError> {
+        for card in &self.cards {
+            card.draw(canvas)?;
+        }
+
+        let mut count = Text::new(format!("{} discarded", self.cards.len()));
+        count
+            .set_font("IBM_CGA")
+            .set_scale(16.)
+            .set_layout(TextLayout::center());
+        canvas.draw(&count, DrawParam::new().dest(self.pos + vec2(0., 16.)));
+
+        Ok(())
+    }
+}
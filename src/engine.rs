@@ -0,0 +1,653 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::Suit,
+    error::DurakError,
+    rules::{self, CardId, Rules},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// Lead a bout or throw in a card of a rank already on the table.
+    Attack(CardId),
+    /// Cover the stack at the given index.
+    Defend(usize, CardId),
+    /// Pass the attack on to the next player by adding a card of the same
+    /// rank, before anything has been covered. Only with transfers allowed.
+    Transfer(CardId),
+    /// Pass the attack on like a transfer by showing a trump of the same rank,
+    /// which stays in the hand. Only with showing trumps allowed.
+    ShowTrump(CardId),
+    /// Give up defending and pick up the table once the attackers are done.
+    Take,
+    /// Stop adding cards until the table changes.
+    Pass,
+}
+
+impl Action {
+    /// The card played by the action, if any.
+    pub fn card(self) -> Option<CardId> {
+        match self {
+            Action::Attack(card)
+            | Action::Defend(_, card)
+            | Action::Transfer(card)
+            | Action::ShowTrump(card) => Some(card),
+            Action::Take | Action::Pass => None,
+        }
+    }
+}
+
+/// Card movements caused by an action, for the table to animate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Attacked(usize, CardId),
+    Defended(usize, usize, CardId),
+    /// The defender passed the attack on with the card and became an attacker.
+    Transferred(usize, CardId),
+    /// The defender passed the attack on by showing the trump.
+    Shown(usize, CardId),
+    /// The defender picked up every card on the table.
+    Took(usize),
+    /// Every attack was covered and the table went to the discard pile.
+    Beaten,
+    Drew(usize, CardId),
+    /// The player got rid of their last card after the deck ran out.
+    Out(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The player left holding cards.
+    Durak(usize),
+    /// In team play, the team whose members were left holding cards.
+    LosingTeam(usize),
+    /// Everyone got rid of their cards in the same bout, with draws allowed.
+    Draw,
+}
+
+/// Headless state of one game of Durak. Hands, deck and table only hold
+/// [`CardId`]s; the game scenes mirror them with sprites by replaying the
+/// [`Event`]s returned from [`Engine::apply`].
+#[derive(Debug, Clone)]
+pub struct Engine {
+    /// The deck as it was before dealing, so the game can be replayed.
+    deal: Vec<CardId>,
+    hands: Vec<Vec<CardId>>,
+    /// Undealt cards. The last one is drawn first, the first one is the
+    /// face up trump card.
+    talon: Vec<CardId>,
+    trump: Suit,
+    table: Vec<(CardId, Option<CardId>)>,
+    discard: Vec<CardId>,
+    teams: Option<Vec<usize>>,
+    attacker: usize,
+    defender: usize,
+    taking: bool,
+    passed: Vec<bool>,
+    out: Vec<bool>,
+    /// Players who got rid of their cards, in the order they did.
+    finished: Vec<usize>,
+    /// Player picked to lead the first bout instead of the lowest trump.
+    leader: Option<usize>,
+    rules: Rules,
+    /// Bouts played to the end so far.
+    bouts: usize,
+    bout_limit: usize,
+    /// Trumps shown to pass on the current bout.
+    shown: Vec<CardId>,
+    log: Vec<(usize, Action)>,
+    outcome: Option<Outcome>,
+}
+
+impl Engine {
+    /// Deals from `talon` and picks the player with the lowest trump to lead.
+    /// `teams` holds each player's team in team play.
+    pub fn new(
+        talon: Vec<CardId>,
+        players: usize,
+        teams: Option<Vec<usize>>,
+        rules: Rules,
+    ) -> Result<(Engine, Vec<Event>), DurakError> {
+        if players < 2 {
+            return Err(DurakError::rules("at least two players are needed"));
+        }
+        if rules.deal_size == 0 || rules.bout_limit == 0 || rules.first_bout_limit == 0 {
+            return Err(DurakError::rules("hands and bouts need at least one card"));
+        }
+        if talon.len() < players * rules.deal_size {
+            return Err(DurakError::state("not enough cards to deal"));
+        }
+        if teams.as_ref().is_some_and(|teams| teams.len() != players) {
+            return Err(DurakError::state("every player needs a team"));
+        }
+
+        let trump = talon[0].suit;
+        let mut engine = Engine {
+            deal: talon.clone(),
+            hands: vec![Vec::new(); players],
+            talon,
+            trump,
+            table: Vec::new(),
+            discard: Vec::new(),
+            teams,
+            attacker: 0,
+            defender: 0,
+            taking: false,
+            passed: vec![false; players],
+            out: vec![false; players],
+            finished: Vec::new(),
+            leader: None,
+            rules,
+            bouts: 0,
+            bout_limit: rules.first_bout_limit,
+            shown: Vec::new(),
+            log: Vec::new(),
+            outcome: None,
+        };
+
+        let mut events = Vec::new();
+        for _ in 0..rules.deal_size {
+            for player in 0..players {
+                if let Some(card) = engine.talon.pop() {
+                    engine.hands[player].push(card);
+                    events.push(Event::Drew(player, card));
+                }
+            }
+        }
+
+        let first = (0..players)
+            .filter_map(|player| {
+                engine.hands[player]
+                    .iter()
+                    .filter(|card| card.suit == trump)
+                    .map(|card| (card.rank, player))
+                    .min()
+            })
+            .min()
+            .map(|(_, player)| player)
+            .unwrap_or_default();
+        engine.start_bout(first);
+
+        Ok((engine, events))
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn players(&self) -> usize {
+        self.hands.len()
+    }
+
+    pub fn hand(&self, player: usize) -> &[CardId] {
+        &self.hands[player]
+    }
+
+    pub fn table(&self) -> &[(CardId, Option<CardId>)] {
+        &self.table
+    }
+
+    pub fn discard(&self) -> &[CardId] {
+        &self.discard
+    }
+
+    pub fn talon(&self) -> &[CardId] {
+        &self.talon
+    }
+
+    pub fn trump(&self) -> Suit {
+        self.trump
+    }
+
+    pub fn attacker(&self) -> usize {
+        self.attacker
+    }
+
+    pub fn defender(&self) -> usize {
+        self.defender
+    }
+
+    pub fn taking(&self) -> bool {
+        self.taking
+    }
+
+    pub fn team(&self, player: usize) -> Option<usize> {
+        self.teams.as_ref().map(|teams| teams[player])
+    }
+
+    pub fn finished(&self) -> &[usize] {
+        &self.finished
+    }
+
+    /// Lets `player` lead the first bout instead of whoever holds the lowest
+    /// trump, e.g. the loser of the previous game. Has no effect once the
+    /// game is under way.
+    pub fn set_leader(&mut self, player: usize) {
+        if self.log.is_empty() && player < self.players() {
+            self.leader = Some(player);
+            self.start_bout(player);
+        }
+    }
+
+    /// Player picked with [`set_leader`](Engine::set_leader), if any.
+    pub fn leader(&self) -> Option<usize> {
+        self.leader
+    }
+
+    /// The deck as it was before dealing, the trump card first and the
+    /// first card dealt last.
+    pub fn deal(&self) -> &[CardId] {
+        &self.deal
+    }
+
+    pub fn is_out(&self, player: usize) -> bool {
+        self.out[player]
+    }
+
+    pub fn log(&self) -> &[(usize, Action)] {
+        &self.log
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// The game as it was after the first `moves` actions of the log, rebuilt
+    /// by dealing again and replaying them.
+    pub fn rewind(&self, moves: usize) -> Result<Engine, DurakError> {
+        let (mut engine, _) = Engine::new(
+            self.deal.clone(),
+            self.players(),
+            self.teams.clone(),
+            self.rules,
+        )?;
+        if let Some(leader) = self.leader {
+            engine.set_leader(leader);
+        }
+        for (player, action) in self.log.iter().take(moves) {
+            engine.apply(*player, *action)?;
+        }
+        Ok(engine)
+    }
+
+    /// Whether `a` and `b` play against each other. Partners never attack
+    /// one another.
+    pub fn opponents(&self, a: usize, b: usize) -> bool {
+        match &self.teams {
+            Some(teams) => teams[a] != teams[b],
+            None => a != b,
+        }
+    }
+
+    /// Players who may add cards to the current bout.
+    fn can_throw_in(&self, player: usize) -> bool {
+        player != self.defender
+            && !self.out[player]
+            && self.opponents(player, self.defender)
+            && (player == self.attacker || !self.table.is_empty())
+            && (player == self.attacker || self.rules.neighbours_throw_in)
+    }
+
+    /// Next player still in who plays against `player`, in turn order.
+    fn next_opponent(&self, player: usize) -> Option<usize> {
+        let players = self.players();
+        (1..players)
+            .map(|i| (player + i) % players)
+            .find(|other| !self.out[*other] && self.opponents(player, *other))
+    }
+
+    fn attacks_allowed(&self) -> bool {
+        let uncovered = self
+            .table
+            .iter()
+            .filter(|(_, cover)| cover.is_none())
+            .count();
+        self.table.len() < self.bout_limit && uncovered < self.hands[self.defender].len()
+    }
+
+    /// Whether the player still has to do something before the bout can go on.
+    pub fn needs_to_act(&self, player: usize) -> bool {
+        if self.outcome.is_some() {
+            return false;
+        }
+        if player == self.defender {
+            !self.taking && self.table.iter().any(|(_, cover)| cover.is_none())
+        } else if self.table.is_empty() {
+            player == self.attacker
+        } else {
+            self.can_throw_in(player) && !self.passed[player] && self.can_add_any(player)
+        }
+    }
+
+    fn can_add_any(&self, player: usize) -> bool {
+        self.hands[player]
+            .iter()
+            .any(|card| self.check(player, Action::Attack(*card)).is_ok())
+    }
+
+    pub fn legal_actions(&self, player: usize) -> Vec<Action> {
+        let mut actions: Vec<Action> = self.hands[player]
+            .iter()
+            .map(|card| Action::Attack(*card))
+            .collect();
+        for stack in 0..self.table.len() {
+            actions.extend(
+                self.hands[player]
+                    .iter()
+                    .map(|card| Action::Defend(stack, *card)),
+            );
+        }
+        actions.extend(
+            self.hands[player]
+                .iter()
+                .flat_map(|card| [Action::Transfer(*card), Action::ShowTrump(*card)]),
+        );
+        actions.extend([Action::Take, Action::Pass]);
+        actions.retain(|action| self.check(player, *action).is_ok());
+        actions
+    }
+
+    /// What playing `card` means for `player`. Attackers attack with it. The
+    /// defender covers `stack`, or else the first attack the card beats, and
+    /// transfers when covering isn't possible but a transfer is, by playing
+    /// the card or else by showing it.
+    pub fn play_card(&self, player: usize, card: CardId, stack: Option<usize>) -> Action {
+        if player != self.defender {
+            return Action::Attack(card);
+        }
+        let uncovered =
+            |stack: &usize| matches!(self.table.get(*stack), Some((_, cover)) if cover.is_none());
+        let defend = stack
+            .filter(uncovered)
+            .or_else(|| {
+                (0..self.table.len()).find(|stack| {
+                    uncovered(stack) && rules::beats(self.table[*stack].0, card, self.trump)
+                })
+            })
+            .map(|stack| Action::Defend(stack, card));
+
+        match defend {
+            Some(action) if self.check(player, action).is_ok() => action,
+            _ if self.check(player, Action::Transfer(card)).is_ok() => Action::Transfer(card),
+            _ if self.check(player, Action::ShowTrump(card)).is_ok() => Action::ShowTrump(card),
+            defend => defend.unwrap_or(Action::Defend(stack.unwrap_or_default(), card)),
+        }
+    }
+
+    /// Checks `action` against the rules without applying it.
+    pub fn check(&self, player: usize, action: Action) -> Result<(), DurakError> {
+        if self.outcome.is_some() {
+            return Err(DurakError::rules("the game is over"));
+        }
+        let holds = |card: CardId| self.hands[player].contains(&card);
+
+        match action {
+            Action::Attack(card) => {
+                if !self.can_throw_in(player) {
+                    return Err(DurakError::rules(if player == self.defender {
+                        "the defender can't attack"
+                    } else if self.table.is_empty() {
+                        "not your turn to lead"
+                    } else if !self.opponents(player, self.defender) {
+                        "you can't throw in on your partner"
+                    } else {
+                        "only the leader can throw in"
+                    }));
+                }
+                if !holds(card) {
+                    return Err(DurakError::rules("card not in hand"));
+                }
+                if !self.attacks_allowed() {
+                    return Err(DurakError::rules("no room for more attacks"));
+                }
+                if !rules::can_attack(self.table_cards(), card) {
+                    return Err(DurakError::rules("rank not on table"));
+                }
+                Ok(())
+            }
+            Action::Defend(stack, card) => {
+                if player != self.defender {
+                    return Err(DurakError::rules("only the defender can cover"));
+                }
+                if self.taking {
+                    return Err(DurakError::rules("already taking"));
+                }
+                if !holds(card) {
+                    return Err(DurakError::rules("card not in hand"));
+                }
+                match self.table.get(stack) {
+                    None => Err(DurakError::rules("no such attack")),
+                    Some((_, Some(_))) => Err(DurakError::rules("already covered")),
+                    Some((attack, None)) if !rules::beats(*attack, card, self.trump) => {
+                        Err(DurakError::rules("card doesn't beat the attack"))
+                    }
+                    Some(_) => Ok(()),
+                }
+            }
+            Action::Transfer(card) | Action::ShowTrump(card) => {
+                let showing = matches!(action, Action::ShowTrump(_));
+                if !showing && !self.rules.transfers {
+                    return Err(DurakError::rules("transfers are not allowed"));
+                }
+                if showing && !self.rules.show_trump {
+                    return Err(DurakError::rules("showing trumps is not allowed"));
+                }
+                if player != self.defender || self.taking {
+                    return Err(DurakError::rules("only the defender can transfer"));
+                }
+                if !holds(card) {
+                    return Err(DurakError::rules("card not in hand"));
+                }
+                if showing && card.suit != self.trump {
+                    return Err(DurakError::rules("only a trump can be shown"));
+                }
+                if showing && self.shown.contains(&card) {
+                    return Err(DurakError::rules("that trump was shown already"));
+                }
+                if self.table.is_empty() || self.table.iter().any(|(_, cover)| cover.is_some()) {
+                    return Err(DurakError::rules("too late to transfer"));
+                }
+                if self
+                    .table
+                    .iter()
+                    .any(|(attack, _)| attack.rank != card.rank)
+                {
+                    return Err(DurakError::rules("transfer needs the same rank"));
+                }
+                // A shown trump doesn't add to the table.
+                let attacks = self.table.len() + usize::from(!showing);
+                match self.next_opponent(player) {
+                    Some(next) if self.hands[next].len() >= attacks => Ok(()),
+                    _ => Err(DurakError::rules("next player can't take that many cards")),
+                }
+            }
+            Action::Take => {
+                if player != self.defender {
+                    return Err(DurakError::rules("only the defender can take"));
+                }
+                if self.taking || self.table.iter().all(|(_, cover)| cover.is_some()) {
+                    return Err(DurakError::rules("nothing to take"));
+                }
+                Ok(())
+            }
+            Action::Pass => {
+                if !self.can_throw_in(player) || self.table.is_empty() {
+                    return Err(DurakError::rules("nothing to pass on"));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Applies `action` for `player`, returning the resulting card movements.
+    pub fn apply(&mut self, player: usize, action: Action) -> Result<Vec<Event>, DurakError> {
+        self.check(player, action)?;
+        self.log.push((player, action));
+
+        let mut events = Vec::new();
+        match action {
+            Action::Attack(card) => {
+                self.remove_from_hand(player, card);
+                self.table.push((card, None));
+                self.passed.fill(false);
+                events.push(Event::Attacked(player, card));
+            }
+            Action::Defend(stack, card) => {
+                self.remove_from_hand(player, card);
+                self.table[stack].1 = Some(card);
+                self.passed.fill(false);
+                events.push(Event::Defended(player, stack, card));
+            }
+            Action::Transfer(card) => {
+                self.remove_from_hand(player, card);
+                self.table.push((card, None));
+                self.pass_on(player);
+                events.push(Event::Transferred(player, card));
+            }
+            Action::ShowTrump(card) => {
+                self.shown.push(card);
+                self.pass_on(player);
+                events.push(Event::Shown(player, card));
+            }
+            Action::Take => self.taking = true,
+            Action::Pass => self.passed[player] = true,
+        }
+
+        if self.bout_over() {
+            self.end_bout(&mut events);
+        }
+        Ok(events)
+    }
+
+    /// Makes the next opponent of `player` defend the bout, with `player`
+    /// attacking.
+    fn pass_on(&mut self, player: usize) {
+        if let Some(next) = self.next_opponent(player) {
+            self.attacker = player;
+            self.defender = next;
+            self.bout_limit = self.max_attacks().min(self.hands[next].len());
+        }
+        self.passed.fill(false);
+    }
+
+    /// Most attacking cards the rules allow in the current bout.
+    fn max_attacks(&self) -> usize {
+        if self.bouts == 0 {
+            self.rules.first_bout_limit
+        } else {
+            self.rules.bout_limit
+        }
+    }
+
+    fn remove_from_hand(&mut self, player: usize, card: CardId) {
+        if let Some(idx) = self.hands[player].iter().position(|other| *other == card) {
+            self.hands[player].remove(idx);
+        }
+    }
+
+    fn table_cards(&self) -> impl Iterator<Item = CardId> + '_ {
+        self.table
+            .iter()
+            .flat_map(|(attack, cover)| [Some(*attack), *cover])
+            .flatten()
+    }
+
+    fn bout_over(&self) -> bool {
+        if self.table.is_empty() {
+            return false;
+        }
+        let covered = self.table.iter().all(|(_, cover)| cover.is_some());
+        let attackers_done = (0..self.players())
+            .filter(|player| self.can_throw_in(*player))
+            .all(|player| self.passed[player] || !self.can_add_any(player));
+        attackers_done && (self.taking || covered)
+    }
+
+    fn end_bout(&mut self, events: &mut Vec<Event>) {
+        let cards: Vec<CardId> = self.table_cards().collect();
+        self.table.clear();
+
+        let next_attacker = if self.taking {
+            self.hands[self.defender].extend(cards);
+            events.push(Event::Took(self.defender));
+            self.defender + 1
+        } else {
+            self.discard.extend(cards);
+            events.push(Event::Beaten);
+            self.defender
+        };
+
+        // Attackers draw first, in turn order, and the defender last.
+        let players = self.players();
+        let order = (0..players)
+            .map(|i| (self.attacker + i) % players)
+            .filter(|player| *player != self.defender)
+            .chain([self.defender]);
+        for player in order.collect::<Vec<_>>() {
+            while self.hands[player].len() < self.rules.deal_size {
+                match self.talon.pop() {
+                    Some(card) => {
+                        self.hands[player].push(card);
+                        events.push(Event::Drew(player, card));
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        for player in 0..players {
+            if !self.out[player] && self.hands[player].is_empty() && self.talon.is_empty() {
+                self.out[player] = true;
+                self.finished.push(player);
+                events.push(Event::Out(player));
+            }
+        }
+
+        let remaining: Vec<usize> = (0..players).filter(|p| !self.out[*p]).collect();
+        self.outcome = match remaining.as_slice() {
+            [] if self.rules.draws => Some(Outcome::Draw),
+            [] => {
+                // Without draws the player who led the last bout loses, so
+                // they don't count as having got rid of their cards.
+                let loser = self.attacker;
+                self.finished.retain(|player| *player != loser);
+                Some(self.loss(loser))
+            }
+            [player] => Some(self.loss(*player)),
+            [first, rest @ ..] if rest.iter().all(|p| !self.opponents(*first, *p)) => {
+                self.team(*first).map(Outcome::LosingTeam)
+            }
+            _ => None,
+        };
+
+        self.bouts += 1;
+        if self.outcome.is_none() {
+            self.start_bout(next_attacker % players);
+        }
+    }
+
+    /// Outcome with `player` left as the durak, or their team losing.
+    fn loss(&self, player: usize) -> Outcome {
+        match self.team(player) {
+            Some(team) => Outcome::LosingTeam(team),
+            None => Outcome::Durak(player),
+        }
+    }
+
+    /// Starts a bout led by the first player still in, starting at `from`.
+    fn start_bout(&mut self, from: usize) {
+        let players = self.players();
+        let attacker = (0..players)
+            .map(|i| (from + i) % players)
+            .find(|player| !self.out[*player])
+            .unwrap_or(from);
+        let defender = self.next_opponent(attacker).unwrap_or(attacker);
+
+        self.attacker = attacker;
+        self.defender = defender;
+        self.taking = false;
+        self.passed.fill(false);
+        self.shown.clear();
+        self.bout_limit = self.max_attacks().min(self.hands[defender].len());
+    }
+}
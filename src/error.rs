@@ -1,30 +1,188 @@
-use ggez::GameError;
+use std::{error::Error, fmt::Display, io};
+#[cfg(feature = "gui")]
+use std::{io::Write, time::SystemTime};
 
+#[cfg(feature = "gui")]
+use ggez::{filesystem::OpenOptions, Context, GameError};
+
+#[cfg(feature = "gui")]
 use crate::scenes::SceneError;
 
 #[derive(Debug)]
-pub struct DurakError(String);
+pub enum DurakError {
+    /// An image, font or other resource is missing or could not be loaded.
+    Asset {
+        name: String,
+        #[cfg(feature = "gui")]
+        source: Option<GameError>,
+    },
+    /// A move was rejected by the rules of the game.
+    Rules(String),
+    Io(io::Error),
+    /// A connection to another player failed.
+    Network {
+        peer: String,
+        source: io::Error,
+    },
+    /// A message from another player or process could not be understood.
+    Protocol(String),
+    /// The game state is inconsistent, e.g. a card went missing.
+    State(String),
+    /// The command line arguments make no sense.
+    Usage(String),
+    /// Any other failure reported by ggez, such as a rendering error.
+    #[cfg(feature = "gui")]
+    Engine(GameError),
+}
+
+impl DurakError {
+    pub fn asset(name: impl Into<String>) -> Self {
+        DurakError::Asset {
+            name: name.into(),
+            #[cfg(feature = "gui")]
+            source: None,
+        }
+    }
+
+    pub fn rules(reason: impl Into<String>) -> Self {
+        DurakError::Rules(reason.into())
+    }
+
+    pub fn protocol(reason: impl Into<String>) -> Self {
+        DurakError::Protocol(reason.into())
+    }
+
+    pub fn state(reason: impl Into<String>) -> Self {
+        DurakError::State(reason.into())
+    }
+
+    pub fn usage(reason: impl Into<String>) -> Self {
+        DurakError::Usage(reason.into())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl DurakError {
+    /// Fatal errors leave the game unable to draw anything useful, so they
+    /// end the event loop instead of showing the error screen. A missing
+    /// asset isn't one of them: the error screen draws without any, and
+    /// broken themes can be added by the player at any time.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, DurakError::Engine(_))
+    }
+
+    /// Appends the error and its sources to `/error.log` in the user directory.
+    pub fn write_log(&self, ctx: &Context) -> Result<(), DurakError> {
+        let mut file = ctx.fs.open_options(
+            "/error.log",
+            OpenOptions::new().write(true).create(true).append(true),
+        )?;
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        writeln!(file, "[{time}] {self}")?;
+        let mut source = self.source();
+        while let Some(error) = source {
+            writeln!(file, "    caused by: {error}")?;
+            source = error.source();
+        }
+        writeln!(file, "    {self:?}")?;
+        Ok(())
+    }
+
+    /// Writes the error to `/error.log`, or to stderr if even that fails.
+    pub fn report(&self, ctx: &Context) {
+        if let Err(log_error) = self.write_log(ctx) {
+            eprintln!("{self} ({log_error} writing error.log)");
+        }
+    }
+}
+
+impl Display for DurakError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DurakError::Asset { name, .. } => write!(f, "cannot load asset {name}"),
+            DurakError::Rules(reason) => write!(f, "illegal move: {reason}"),
+            DurakError::Io(_) => write!(f, "I/O error"),
+            DurakError::Network { peer, .. } => write!(f, "connection to {peer} failed"),
+            DurakError::Protocol(reason) => write!(f, "protocol error: {reason}"),
+            DurakError::State(reason) => write!(f, "inconsistent game state: {reason}"),
+            DurakError::Usage(reason) => write!(f, "invalid arguments: {reason}"),
+            #[cfg(feature = "gui")]
+            DurakError::Engine(_) => write!(f, "engine error"),
+        }
+    }
+}
+
+impl Error for DurakError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "gui")]
+            DurakError::Asset {
+                source: Some(source),
+                ..
+            } => Some(source),
+            DurakError::Io(source) | DurakError::Network { source, .. } => Some(source),
+            #[cfg(feature = "gui")]
+            DurakError::Engine(source) => Some(source),
+            _ => None,
+        }
+    }
+}
 
-impl From<&str> for DurakError {
-    fn from(value: &str) -> Self {
-        DurakError(value.to_string())
+impl From<io::Error> for DurakError {
+    fn from(value: io::Error) -> Self {
+        DurakError::Io(value)
     }
 }
 
+#[cfg(feature = "gui")]
 impl From<GameError> for DurakError {
     fn from(value: GameError) -> Self {
-        DurakError(format!("{:?}", value))
+        match value {
+            GameError::ResourceNotFound(ref name, _) => DurakError::Asset {
+                name: name.clone(),
+                source: Some(value),
+            },
+            GameError::ResourceLoadError(ref reason) => DurakError::Asset {
+                name: reason.clone(),
+                source: Some(value),
+            },
+            GameError::FontError(_) => DurakError::Asset {
+                name: String::from("font"),
+                source: Some(value),
+            },
+            value => DurakError::Engine(value),
+        }
     }
 }
 
+#[cfg(feature = "gui")]
 impl From<SceneError> for DurakError {
     fn from(value: SceneError) -> Self {
-        DurakError(format!("{:?}", value))
+        match value {
+            SceneError::SceneMissing => DurakError::state("no active scene"),
+            SceneError::Render(error) => error.into(),
+        }
     }
 }
 
+#[cfg(feature = "gui")]
 impl From<DurakError> for GameError {
     fn from(value: DurakError) -> Self {
-        GameError::CustomError(format!("{:?}", value))
+        match value {
+            DurakError::Engine(error) => error,
+            value => {
+                let mut message = value.to_string();
+                let mut source = value.source();
+                while let Some(error) = source {
+                    message.push_str(&format!(": {error}"));
+                    source = error.source();
+                }
+                GameError::CustomError(message)
+            }
+        }
     }
 }
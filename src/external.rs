@@ -0,0 +1,503 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, TcpStream},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{Action, Engine},
+    error::DurakError,
+    rules::{self, CardId},
+};
+
+/// Time a program gets for every move.
+pub const MOVE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Pause between looks for an answer while waiting for one.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// A move as written in the protocol, cards by name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Move {
+    Attack { card: String },
+    Defend { stack: usize, card: String },
+    Transfer { card: String },
+    ShowTrump { card: String },
+    Take,
+    Pass,
+}
+
+impl From<Action> for Move {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Attack(card) => Move::Attack {
+                card: card.to_string(),
+            },
+            Action::Defend(stack, card) => Move::Defend {
+                stack,
+                card: card.to_string(),
+            },
+            Action::Transfer(card) => Move::Transfer {
+                card: card.to_string(),
+            },
+            Action::ShowTrump(card) => Move::ShowTrump {
+                card: card.to_string(),
+            },
+            Action::Take => Move::Take,
+            Action::Pass => Move::Pass,
+        }
+    }
+}
+
+impl Move {
+    /// The action of `player` this move stands for, its card looked up in
+    /// their hand.
+    fn action(&self, engine: &Engine, player: usize) -> Result<Action, DurakError> {
+        let card = |text: &str| {
+            let (rank, suit) = rules::parse_card(text)
+                .ok_or_else(|| DurakError::protocol(format!("{text} is not a card")))?;
+            engine
+                .hand(player)
+                .iter()
+                .find(|card| card.rank == rank && card.suit == suit)
+                .copied()
+                .ok_or_else(|| DurakError::protocol(format!("{text} is not in the hand")))
+        };
+        Ok(match self {
+            Move::Attack { card: text } => Action::Attack(card(text)?),
+            Move::Defend { stack, card: text } => Action::Defend(*stack, card(text)?),
+            Move::Transfer { card: text } => Action::Transfer(card(text)?),
+            Move::ShowTrump { card: text } => Action::ShowTrump(card(text)?),
+            Move::Take => Action::Take,
+            Move::Pass => Action::Pass,
+        })
+    }
+}
+
+/// The game as seen from the seat of a player who has to move.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub id: u64,
+    pub player: usize,
+    pub players: usize,
+    pub trump: char,
+    /// The face up card at the bottom of the deck, while there is one.
+    pub bottom: Option<String>,
+    pub talon: usize,
+    pub hand: Vec<String>,
+    /// Number of cards in every hand.
+    pub hands: Vec<usize>,
+    pub table: Vec<(String, Option<String>)>,
+    pub discarded: usize,
+    pub attacker: usize,
+    pub defender: usize,
+    pub legal: Vec<Move>,
+}
+
+impl Request {
+    fn new(id: u64, engine: &Engine, player: usize) -> Request {
+        let names = |cards: &[CardId]| cards.iter().map(CardId::to_string).collect();
+        Request {
+            id,
+            player,
+            players: engine.players(),
+            trump: engine.trump().symbol(),
+            bottom: engine.talon().first().map(CardId::to_string),
+            talon: engine.talon().len(),
+            hand: names(engine.hand(player)),
+            hands: (0..engine.players())
+                .map(|other| engine.hand(other).len())
+                .collect(),
+            table: engine
+                .table()
+                .iter()
+                .map(|(attack, cover)| (attack.to_string(), cover.map(|card| card.to_string())))
+                .collect(),
+            discarded: engine.discard().len(),
+            attacker: engine.attacker(),
+            defender: engine.defender(),
+            legal: engine
+                .legal_actions(player)
+                .into_iter()
+                .map(Move::from)
+                .collect(),
+        }
+    }
+
+    /// The table as seen from the seat, a line at a time, for a person to
+    /// read.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Trump {}{}, {} cards left, {} discarded",
+            self.trump,
+            self.bottom
+                .as_ref()
+                .map(|card| format!(" ({card} at the bottom)"))
+                .unwrap_or_default(),
+            self.talon,
+            self.discarded
+        )];
+        for (player, cards) in self.hands.iter().enumerate() {
+            let name = if player == self.player {
+                String::from("You")
+            } else {
+                format!("Player {}", player + 1)
+            };
+            let role = if player == self.defender {
+                " (defending)"
+            } else if player == self.attacker {
+                " (attacking)"
+            } else {
+                ""
+            };
+            lines.push(format!("{name}: {cards} cards{role}"));
+        }
+        let stacks: Vec<String> = self
+            .table
+            .iter()
+            .enumerate()
+            .map(|(stack, (attack, cover))| match cover {
+                Some(cover) => format!("{}) {attack}/{cover}", stack + 1),
+                None => format!("{}) {attack}", stack + 1),
+            })
+            .collect();
+        lines.push(format!("Table: {}", stacks.join("  ")));
+        lines.push(format!("Your hand: {}", self.hand.join(" ")));
+        lines
+    }
+
+    /// Short name of `action`, one of the legal moves, e.g. `cover 7♥
+    /// with 9♥`.
+    pub fn label(&self, action: &Move) -> String {
+        match action {
+            Move::Attack { card } => format!("attack {card}"),
+            Move::Defend { stack, card } => match self.table.get(*stack) {
+                Some((attack, _)) => format!("cover {attack} with {card}"),
+                None => format!("cover {} with {card}", stack + 1),
+            },
+            Move::Transfer { card } => format!("transfer {card}"),
+            Move::ShowTrump { card } => format!("show {card}"),
+            Move::Take => String::from("take"),
+            Move::Pass => String::from("pass"),
+        }
+    }
+}
+
+/// The answer to a [`Request`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reply {
+    pub id: u64,
+    #[serde(flatten)]
+    pub action: Move,
+}
+
+/// Sent once the game is over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finished {
+    /// Who lost, e.g. `Bot 1 is the durak`.
+    pub result: String,
+}
+
+/// A bot played by a child process, so bots can be written in any language.
+///
+/// The program reads requests from stdin and answers on stdout, one JSON
+/// object per line. Whenever the bot has to act it gets the state of the
+/// game as seen from its seat, with the moves it may make:
+///
+/// ```json
+/// {"id":3,"player":1,"players":2,"trump":"♠","bottom":"7♠","talon":12,
+///  "hand":["6♥","10♣","9♥"],"hands":[5,3],"table":[["7♥",null]],
+///  "discarded":4,"attacker":0,"defender":1,
+///  "legal":[{"action":"defend","stack":0,"card":"9♥"},{"action":"take"}]}
+/// ```
+///
+/// and answers with one of them, repeating the request's `id`:
+///
+/// ```json
+/// {"id":3,"action":"defend","stack":0,"card":"9♥"}
+/// ```
+///
+/// Actions are `attack`, `defend`, `transfer`, `show_trump`, `take` and
+/// `pass`. Cards are written like `10♥`; the suit may also be given by its
+/// initial, as in `10h`. Answers to earlier requests are ignored. A program
+/// that answers too late, makes an illegal move or quits is covered for by
+/// the built-in bot. Once the game is over the program may be told the
+/// result, as in `{"result":"Bot 1 is the durak"}`, before it is stopped.
+///
+/// A player who joined over the network is played the same way, the lines
+/// going over the connection instead.
+pub struct ExternalBot {
+    peer: Peer,
+    /// Lines for the program, written on a thread of their own so a program
+    /// that stops reading can't hold up the game.
+    requests: Sender<String>,
+    /// Lines the program wrote, read on a thread of their own so waiting for
+    /// them can time out.
+    replies: Receiver<String>,
+    timeout: Duration,
+    next_id: u64,
+    pending: Option<Pending>,
+    quit: bool,
+}
+
+/// Whoever answers the requests.
+enum Peer {
+    Program(Child),
+    Guest(TcpStream),
+}
+
+/// A request being waited for.
+struct Pending {
+    id: u64,
+    /// Who was asked to move.
+    player: usize,
+    /// Moves made before the request, which tell its position apart from
+    /// any other, even after moves were taken back.
+    log: Vec<(usize, Action)>,
+    sent: Instant,
+}
+
+impl ExternalBot {
+    /// Starts `command`, a program followed by its arguments as split by
+    /// [`split_command`], which gets `timeout` for every move.
+    pub fn spawn(command: &str, timeout: Duration) -> Result<ExternalBot, DurakError> {
+        let words = split_command(command)?;
+        let (program, args) = words
+            .split_first()
+            .ok_or_else(|| DurakError::protocol("no bot program given"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(DurakError::protocol("no pipes to the bot program"));
+        };
+        Ok(ExternalBot::start(
+            Peer::Program(child),
+            BufReader::new(stdout),
+            stdin,
+            timeout,
+        ))
+    }
+
+    /// Plays the seat of a player connected by `reader`, which may already
+    /// hold what they sent before the game started.
+    pub(crate) fn guest(
+        reader: BufReader<TcpStream>,
+        timeout: Duration,
+    ) -> Result<ExternalBot, DurakError> {
+        let stream = reader.get_ref().try_clone()?;
+        let writer = stream.try_clone()?;
+        Ok(ExternalBot::start(
+            Peer::Guest(stream),
+            reader,
+            writer,
+            timeout,
+        ))
+    }
+
+    fn start(
+        peer: Peer,
+        reader: impl BufRead + Send + 'static,
+        mut writer: impl Write + Send + 'static,
+        timeout: Duration,
+    ) -> ExternalBot {
+        let (requests, outbox) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in outbox {
+                if writeln!(writer, "{line}")
+                    .and_then(|()| writer.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        ExternalBot {
+            peer,
+            requests,
+            replies,
+            timeout,
+            next_id: 0,
+            pending: None,
+            quit: false,
+        }
+    }
+
+    /// The program's move for `player`, asked for on the first call and
+    /// picked up by later ones, which return `None` until it arrives. Fails
+    /// when the program is out of time, answers with an illegal move or has
+    /// quit; the caller then moves for it.
+    pub fn poll(&mut self, engine: &Engine, player: usize) -> Result<Option<Action>, DurakError> {
+        if self.quit {
+            return Err(self.gone());
+        }
+        let (id, sent) = match &self.pending {
+            Some(pending) if pending.player == player && pending.log == engine.log() => {
+                (pending.id, pending.sent)
+            }
+            _ => self.request(engine, player)?,
+        };
+
+        loop {
+            let line = match self.replies.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.quit = true;
+                    return Err(self.gone());
+                }
+            };
+            let reply: Reply = match serde_json::from_str(&line) {
+                Ok(reply) => reply,
+                Err(error) => {
+                    self.pending = None;
+                    return Err(DurakError::protocol(format!("{line}: {error}")));
+                }
+            };
+            if reply.id != id {
+                continue;
+            }
+            self.pending = None;
+            let action = reply.action.action(engine, player)?;
+            return match engine.check(player, action) {
+                Ok(()) => Ok(Some(action)),
+                Err(DurakError::Rules(reason)) => Err(DurakError::protocol(format!(
+                    "illegal move {line}: {reason}"
+                ))),
+                Err(error) => Err(error),
+            };
+        }
+
+        if sent.elapsed() > self.timeout {
+            self.pending = None;
+            return Err(DurakError::protocol(format!(
+                "no move within {:.1} s",
+                self.timeout.as_secs_f32()
+            )));
+        }
+        Ok(None)
+    }
+
+    /// Waits for the program's move for `player`, for games without a frame
+    /// loop.
+    pub fn play(&mut self, engine: &Engine, player: usize) -> Result<Action, DurakError> {
+        loop {
+            if let Some(action) = self.poll(engine, player)? {
+                return Ok(action);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Tells the program the game is over and who lost.
+    pub fn finish(&mut self, result: &str) {
+        let finished = Finished {
+            result: result.to_string(),
+        };
+        if let Ok(line) = serde_json::to_string(&finished) {
+            // A program that has quit needn't be told.
+            let _ = self.requests.send(line);
+        }
+    }
+
+    /// Whether the program has quit or the player has left, so nothing more
+    /// will come from them.
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    fn gone(&self) -> DurakError {
+        DurakError::protocol(match self.peer {
+            Peer::Program(_) => "the bot program has quit",
+            Peer::Guest(_) => "the player has left",
+        })
+    }
+
+    fn request(&mut self, engine: &Engine, player: usize) -> Result<(u64, Instant), DurakError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = serde_json::to_string(&Request::new(id, engine, player))
+            .map_err(|error| DurakError::protocol(error.to_string()))?;
+        if self.requests.send(request).is_err() {
+            self.quit = true;
+            return Err(self.gone());
+        }
+        let sent = Instant::now();
+        self.pending = Some(Pending {
+            id,
+            player,
+            log: engine.log().to_vec(),
+            sent,
+        });
+        Ok((id, sent))
+    }
+}
+
+/// Splits a command line into a program and its arguments the way a shell
+/// does: words are separated by spaces, which quotes or a backslash keep in
+/// a word, as in `"/home/me/My Bots/bot" --level 'very hard'`. Within
+/// double quotes a backslash only escapes a quote or another backslash.
+pub fn split_command(command: &str) -> Result<Vec<String>, DurakError> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') if c == '"' && matches!(chars.peek(), Some('"' | '\\')) => {
+                            word.extend(chars.next())
+                        }
+                        Some(other) => word.push(other),
+                        None => {
+                            return Err(DurakError::usage(format!(
+                                "missing closing {c} in {command}"
+                            )))
+                        }
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        // The program or player may be gone already.
+        match &mut self.peer {
+            Peer::Program(child) => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            // Only stops the reading, so the writer thread still gets to send
+            // what is left before the connection closes.
+            Peer::Guest(stream) => {
+                let _ = stream.shutdown(Shutdown::Read);
+            }
+        }
+    }
+}
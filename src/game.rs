@@ -1,17 +1,29 @@
 use std::fmt::Debug;
 
 use ggegui::Gui;
-use ggez::{event::EventHandler, graphics::FontData, Context, GameResult};
+use ggez::{event::EventHandler, Context};
 
 use crate::{
     card::Card,
+    cli::{Options, DEFAULT_NAME},
+    clock::{TimeControl, TimeoutAction},
     deck::Deck,
+    discard::DiscardPile,
+    engine::Outcome,
     error::DurakError,
-    game_scenes::MainMenu,
+    external::ExternalBot,
+    game_scenes::{ErrorScreen, GamePlay, MainMenu},
     hand::Hand,
     mat::Mat,
     player::Player,
+    presets::Presets,
+    remote::RemoteGame,
+    replay::Replay,
+    rules::{DeckSize, Rules},
     scenes::{Scene, SceneError, SceneWrapper},
+    score::Tally,
+    settings::Settings,
+    storage::Assets,
 };
 
 pub struct DurakState {
@@ -19,56 +31,169 @@ pub struct DurakState {
     pub players: Vec<Player>,
     pub deck: Option<Deck>,
     pub mat: Option<Mat>,
-    pub discard_pile: Vec<Card>,
+    pub discard_pile: DiscardPile,
     pub held_card: Option<Card>,
     pub gui: Gui,
+    pub assets: Assets,
+    pub theme: String,
+    /// Number of full card sets shuffled together, each with its own back.
+    pub decks: usize,
+    pub deck_size: DeckSize,
+    /// Partners sit opposite each other and win or lose together.
+    pub teams: bool,
+    pub outcome: Option<Outcome>,
+    /// Let the advisor suggest moves to the local player.
+    pub hints: bool,
+    pub time_control: TimeControl,
+    pub timeout_action: TimeoutAction,
+    pub tally: Tally,
+    pub rules: Rules,
+    /// Name of the preset `rules` started from.
+    pub preset: String,
+    pub presets: Presets,
+    /// Deals and bot moves follow this seed instead of being random.
+    pub seed: Option<u64>,
+    /// Saved game the next deal plays back.
+    pub replay: Option<Replay>,
+    /// Player who joined over the network, and their seat, while no game
+    /// holds them.
+    pub guest: Option<(usize, ExternalBot)>,
+    /// Address of a game hosted elsewhere to join.
+    pub join: Option<String>,
+    pub settings: Settings,
 }
 
 impl DurakState {
-    pub fn new(ctx: &Context) -> Result<Self, DurakError> {
+    pub fn new(ctx: &Context, settings: Settings) -> Result<Self, DurakError> {
+        // A broken presets file leaves the built-in presets to choose from.
+        let presets = Presets::load(ctx).unwrap_or_else(|error| {
+            error.report(ctx);
+            Presets::default()
+        });
         Ok(Self {
             times_played: 0,
             players: vec![
                 Player {
-                    name: String::new(),
+                    name: settings.player_name.clone(),
                     hand: Hand::new(),
                     human: true,
+                    team: 0,
+                    program: None,
                 },
                 Player {
                     name: String::from("Opponent"),
                     hand: Hand::new(),
                     human: false,
+                    team: 1,
+                    program: None,
                 },
             ],
             deck: None,
             mat: None,
-            discard_pile: Vec::new(),
+            discard_pile: DiscardPile::default(),
             held_card: None,
             gui: Gui::new(ctx),
+            assets: Assets::new(),
+            theme: settings.theme.clone(),
+            decks: 1,
+            deck_size: DeckSize::Standard,
+            teams: false,
+            outcome: None,
+            hints: false,
+            time_control: TimeControl::Off,
+            timeout_action: TimeoutAction::TakeOrPass,
+            tally: Tally::default(),
+            rules: presets.get(&settings.preset).unwrap_or_default(),
+            preset: settings.preset.clone(),
+            presets,
+            seed: None,
+            replay: None,
+            guest: None,
+            join: None,
+            settings,
         })
     }
 }
 
+/// Picks the scene to carry on with after `E`, given the state of the scene
+/// that failed if it could be saved.
+type Recover<T, E> =
+    fn(E, Option<T>, &mut Context) -> Result<Box<dyn Scene<State = T, Error = E>>, E>;
+
 pub struct Game<T, E: Debug>
 where
     E: From<SceneError> + Debug,
 {
     scene: SceneWrapper<T, E>,
     gui: Gui,
+    recover: Recover<T, E>,
+}
+
+impl<T, E> Game<T, E>
+where
+    E: From<SceneError> + Debug,
+{
+    /// Replaces the current scene with the one returned by `recover` when
+    /// `result` is an error, handing it the failed scene's state. Errors
+    /// `recover` can't handle end the event loop.
+    fn recover(&mut self, result: Result<(), E>, ctx: &mut Context) -> Result<(), E> {
+        if let Err(error) = result {
+            let state = self.scene.take_state();
+            self.scene = SceneWrapper::new((self.recover)(error, state, ctx)?);
+        }
+        Ok(())
+    }
 }
 
 impl Game<DurakState, DurakError> {
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        ctx.gfx
-            .add_font("IBM_CGA", FontData::from_path(ctx, "/Px437_IBM_CGA.ttf")?);
+    /// Starts at the main menu, or right in a game when `options` ask for one.
+    pub fn new(
+        ctx: &mut Context,
+        settings: Settings,
+        options: &Options,
+    ) -> Result<Self, DurakError> {
+        let mut state = DurakState::new(ctx, settings)?;
+        state
+            .assets
+            .add_font(ctx, "IBM_CGA", "/Px437_IBM_CGA.ttf")?;
+        options.apply(&mut state)?;
 
-        let state = DurakState::new(ctx)?;
-        let scene = Box::new(MainMenu::new(state, ctx)?);
+        let scene: Box<dyn Scene<State = DurakState, Error = DurakError>> = if state.join.is_some()
+        {
+            Box::new(RemoteGame::new(state, ctx).map_err(|(error, _)| error)?)
+        } else if options.quick_start() {
+            Box::new(GamePlay::new(state, ctx).map_err(|(error, _)| error)?)
+        } else {
+            Box::new(MainMenu::new(state, ctx).map_err(|(error, _)| error)?)
+        };
         Ok(Game {
             scene: SceneWrapper::new(scene),
             gui: Gui::new(ctx),
+            recover: Self::show_error,
         })
     }
+
+    fn show_error(
+        error: DurakError,
+        state: Option<DurakState>,
+        ctx: &mut Context,
+    ) -> Result<Box<dyn Scene<State = DurakState, Error = DurakError>>, DurakError> {
+        error.report(ctx);
+        if error.is_fatal() {
+            return Err(error);
+        }
+
+        if let Some(state) = state {
+            return Ok(Box::new(ErrorScreen::with_error(state, &error, false)));
+        }
+        // The failed scene took the game state with it, so start over from
+        // the saved settings.
+        let mut state = DurakState::new(ctx, Settings::load(ctx).unwrap_or_default())?;
+        if state.players[0].name.is_empty() {
+            state.players[0].name = String::from(DEFAULT_NAME);
+        }
+        Ok(Box::new(ErrorScreen::with_error(state, &error, true)))
+    }
 }
 
 impl<T, E> EventHandler<E> for Game<T, E>
@@ -76,11 +201,13 @@ where
     E: From<SceneError> + Debug,
 {
     fn update(&mut self, ctx: &mut ggez::Context) -> Result<(), E> {
-        self.scene.update(&mut self.gui, ctx)
+        let result = self.scene.update(&mut self.gui, ctx);
+        self.recover(result, ctx)
     }
 
     fn draw(&mut self, ctx: &mut ggez::Context) -> Result<(), E> {
-        self.scene.draw(&self.gui, ctx)
+        let result = self.scene.draw(&self.gui, ctx);
+        self.recover(result, ctx)
     }
 
     fn text_input_event(&mut self, _ctx: &mut ggez::Context, character: char) -> Result<(), E> {
@@ -95,7 +222,8 @@ where
         dx: f32,
         dy: f32,
     ) -> Result<(), E> {
-        self.scene.mouse_motion_event(x, y, dx, dy, ctx)
+        let result = self.scene.mouse_motion_event(x, y, dx, dy, ctx);
+        self.recover(result, ctx)
     }
     fn mouse_button_down_event(
         &mut self,
@@ -104,7 +232,8 @@ where
         x: f32,
         y: f32,
     ) -> Result<(), E> {
-        self.scene.mouse_button_down_event(x, y, ctx)
+        let result = self.scene.mouse_button_down_event(x, y, ctx);
+        self.recover(result, ctx)
     }
     fn mouse_button_up_event(
         &mut self,
@@ -113,6 +242,7 @@ where
         x: f32,
         y: f32,
     ) -> Result<(), E> {
-        self.scene.mouse_button_up_event(x, y, ctx)
+        let result = self.scene.mouse_button_up_event(x, y, ctx);
+        self.recover(result, ctx)
     }
 }
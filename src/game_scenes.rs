@@ -1,27 +1,60 @@
-use ggegui::{egui::Area, Gui};
+use std::{collections::HashMap, time::Duration};
+
+use ggegui::{
+    egui::{
+        Area, Button, Checkbox, Color32, ComboBox, DragValue, RadioButton, Slider, TextEdit, Ui,
+        Window,
+    },
+    Gui,
+};
 use ggez::{
-    glam::{vec2, Vec2},
-    graphics::{Canvas, Color, DrawMode, DrawParam, Drawable, Mesh, Rect},
+    glam::vec2,
+    graphics::{Canvas, Color, DrawParam, Rect},
     winit::dpi::PhysicalSize,
     Context,
 };
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
+    bot::{self, Difficulty},
+    card::{Card, Suit, CARD_WIDTH},
+    cards::Cards,
+    clock::{Clocks, TimeControl, TimeoutAction},
     deck::Deck,
+    discard::DiscardPile,
+    engine::{Action, Engine, Event, Outcome},
     error::DurakError,
+    external::{self, ExternalBot, MOVE_TIMEOUT},
     game::DurakState,
     hand::Hand,
     mat::Mat,
-    player::Player,
-    scenes::{Scene, SceneResult, SceneTransition},
-    storage,
+    player::{self, Player},
+    remote::RemoteGame,
+    replay::Replay,
+    rules::{self, CardId, DeckSize, Rules},
+    scenes::{
+        NewScene, Scene, SceneResult, SceneSwitch, SceneTransition, SlideDirection, Transition,
+    },
+    score::{MatchGoal, Scoring, Tally},
+    seating::{Seat, MAX_SEATS},
+    settings::{Settings, ANIMATION_SPEEDS, WINDOW_HEIGHTS, WINDOW_WIDTHS},
+    theme::{Theme, DEFAULT_THEME},
+    tutorial::Tutorial,
 };
 
 pub trait DurakSceneTransition<U: Scene<State = DurakState, Error = DurakError>>:
     SceneTransition<U, DurakState>
 {
-    fn transition(self: Box<Self>, ctx: &Context) -> Result<U, <U as Scene>::Error> {
-        <Self as SceneTransition<U, DurakState>>::transition(self, ctx)
+    fn effect() -> Transition {
+        Transition::Cut
+    }
+
+    fn switch(self: Box<Self>, ctx: &Context) -> SceneResult<Self>
+    where
+        Self: Scene<Error = DurakError>,
+        U: 'static,
+    {
+        <Self as SceneTransition<U, DurakState>>::switch(self, ctx)
     }
 }
 impl<S, U> SceneTransition<U, DurakState> for S
@@ -29,16 +62,144 @@ where
     S: Scene<State = DurakState, Error = DurakError> + DurakSceneTransition<U>,
     U: Scene<State = DurakState, Error = DurakError>,
 {
+    fn effect(state: &DurakState) -> Transition {
+        <Self as DurakSceneTransition<U>>::effect().faster(state.settings.animation_speed)
+    }
 }
 
-impl DurakSceneTransition<GamePlay> for MainMenu {}
-impl DurakSceneTransition<GameOver> for GamePlay {}
+impl DurakSceneTransition<GamePlay> for MainMenu {
+    fn effect() -> Transition {
+        Transition::Fade(Duration::from_millis(400))
+    }
+}
+impl DurakSceneTransition<GameOver> for GamePlay {
+    fn effect() -> Transition {
+        Transition::Slide(Duration::from_millis(500), SlideDirection::Up)
+    }
+}
 impl DurakSceneTransition<MainMenu> for GamePlay {}
-impl DurakSceneTransition<MainMenu> for GameOver {}
+impl DurakSceneTransition<MainMenu> for GameOver {
+    fn effect() -> Transition {
+        Transition::Fade(Duration::from_millis(400))
+    }
+}
+impl DurakSceneTransition<Tutorial> for MainMenu {
+    fn effect() -> Transition {
+        Transition::Fade(Duration::from_millis(400))
+    }
+}
+impl DurakSceneTransition<MainMenu> for Tutorial {
+    fn effect() -> Transition {
+        Transition::Fade(Duration::from_millis(400))
+    }
+}
+impl DurakSceneTransition<SettingsScreen> for MainMenu {}
+impl DurakSceneTransition<MainMenu> for SettingsScreen {}
+impl DurakSceneTransition<MainMenu> for RemoteGame {}
+impl DurakSceneTransition<MainMenu> for ErrorScreen {}
+impl DurakSceneTransition<GamePlay> for ErrorScreen {}
+
+/// File name of a theme without directory and extension.
+fn theme_label(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.strip_suffix(".toml").unwrap_or(name)
+}
+
+/// Reorders `players` so that teams alternate around the table, keeping the
+/// first player in place. Partners then never sit next to each other.
+fn seat_teams(players: &mut Vec<Player>) {
+    let first = players[0].team;
+    let (mut ours, mut theirs): (Vec<Player>, Vec<Player>) =
+        players.drain(..).partition(|player| player.team == first);
+    ours.reverse();
+    theirs.reverse();
+    while let Some(player) = ours.pop() {
+        players.push(player);
+        players.extend(theirs.pop());
+    }
+}
+
+/// Names of the two teams in team play.
+const TEAM_NAMES: [&str; 2] = ["Team A", "Team B"];
+
+/// Length of a match when playing to a number of games is first picked.
+const DEFAULT_MATCH_GAMES: u32 = 5;
+/// Target when playing to a score is first picked.
+const DEFAULT_MATCH_POINTS: u32 = 10;
+/// Time for a move when per-move timers are first picked.
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(20);
+/// Time for a whole game when chess clocks are first picked.
+const DEFAULT_GAME_TIME: Duration = Duration::from_secs(180);
+
+/// Points of every player so far and, once the match is decided, who won it.
+fn scoreboard(ui: &mut Ui, tally: &Tally) {
+    let scores = tally.scores();
+    if scores.is_empty() {
+        return;
+    }
+    ui.label(match tally.goal {
+        MatchGoal::Single => format!("Scores after {} games", tally.games()),
+        MatchGoal::Games(games) => format!("Match, game {} of {games}", tally.games()),
+        MatchGoal::Points(points) => format!("Match to {points} points"),
+    });
+    for (name, points) in &scores {
+        ui.label(format!("{name}: {points}"));
+    }
+    if tally.is_over() {
+        let winners: Vec<&str> = tally.winners().into_iter().map(|(name, _)| name).collect();
+        ui.colored_label(
+            Color32::LIGHT_GREEN,
+            match winners.as_slice() {
+                [winner] => format!("{winner} wins the match"),
+                _ => format!("The match is tied between {}", winners.join(" and ")),
+            },
+        );
+    } else if let MatchGoal::Points(points) = tally.goal {
+        let winners = tally.winners();
+        if winners.len() > 1 && winners[0].1 >= points {
+            ui.label("Tied for the lead, the match goes on");
+        }
+    }
+}
 
 pub struct MainMenu {
     state: DurakState,
     no_of_players: usize,
+    themes: Vec<String>,
+    /// Name to save the edited rules under.
+    preset_name: String,
+    message: Option<String>,
+}
+
+impl MainMenu {
+    fn max_players(&self) -> usize {
+        rules::max_players(
+            self.state.deck_size,
+            self.state.decks,
+            self.state.rules.deal_size,
+        )
+        .min(MAX_SEATS)
+    }
+
+    /// Saves the rules being edited as a preset of the player's.
+    fn save_preset(&mut self, ctx: &Context) -> Result<(), DurakError> {
+        let name = self.preset_name.trim().to_string();
+        self.state.presets.insert(&name, self.state.rules)?;
+        self.state.presets.save(ctx)?;
+        self.state.preset = name;
+        Ok(())
+    }
+}
+
+/// Edits every house rule of `rules`.
+fn rules_editor(ui: &mut Ui, rules: &mut Rules) {
+    ui.add(Slider::new(&mut rules.deal_size, 1..=10).text("Cards dealt"));
+    ui.add(Slider::new(&mut rules.bout_limit, 1..=10).text("Attacks per bout"));
+    ui.add(Slider::new(&mut rules.first_bout_limit, 1..=10).text("Attacks in the first bout"));
+    ui.checkbox(&mut rules.transfers, "Transfers");
+    ui.checkbox(&mut rules.show_trump, "Transfer by showing a trump");
+    ui.checkbox(&mut rules.neighbours_throw_in, "Everyone may throw in");
+    ui.checkbox(&mut rules.draws, "Draws");
 }
 
 impl Scene for MainMenu {
@@ -46,46 +207,263 @@ impl Scene for MainMenu {
     type Error = DurakError;
 
     fn update(mut self: Box<Self>, gui: &mut Gui, ctx: &mut Context) -> SceneResult<Self> {
-        let next = Area::new("id")
+        let theme = match self.state.assets.load::<Theme>(ctx, &self.state.theme) {
+            Ok(theme) => Ok(theme),
+            Err(error) => {
+                error.report(ctx);
+                self.message = Some(format!("{error}, using the classic theme"));
+                self.state.theme = String::from(DEFAULT_THEME);
+                self.state.assets.load::<Theme>(ctx, DEFAULT_THEME)
+            }
+        };
+        let theme = theme.and_then(|theme| {
+            self.state
+                .assets
+                .get(theme)
+                .ok_or_else(|| DurakError::asset(&self.state.theme))
+        });
+        let theme = match theme {
+            Ok(theme) => theme,
+            Err(error) => return Ok(SceneSwitch::Failed(self.take_state(), error)),
+        };
+        let supported = DeckSize::ALL.map(|size| theme.supports(size));
+        if !theme.supports(self.state.deck_size) {
+            self.state.deck_size = DeckSize::Standard;
+        }
+        let (next, tutorial, settings, save_preset) = Area::new("id")
             .show(&gui.ctx(), |ui| {
                 ui.label("Main Menu");
                 ui.label(format!("{} times played", &self.state.times_played));
+                let teams = self.state.teams;
                 for player in &mut self.state.players[0..self.no_of_players] {
-                    let name = &mut player.name;
-                    if ui.text_edit_singleline(name).changed() {};
+                    ui.horizontal(|ui| {
+                        let name = &mut player.name;
+                        if ui.text_edit_singleline(name).changed() {};
+                        if teams {
+                            ui.radio_value(&mut player.team, 0, TEAM_NAMES[0]);
+                            ui.radio_value(&mut player.team, 1, TEAM_NAMES[1]);
+                        }
+                        if !player.human {
+                            let mut program = player.program.clone().unwrap_or_default();
+                            let edit = TextEdit::singleline(&mut program).hint_text("built-in bot");
+                            if ui.add(edit).changed() {
+                                player.program = (!program.trim().is_empty()).then_some(program);
+                            }
+                            if let Some(Err(error)) =
+                                player.program.as_deref().map(external::split_command)
+                            {
+                                ui.colored_label(Color32::LIGHT_RED, error.to_string());
+                            }
+                        }
+                    });
+                }
+                if let Some(name) = player::shared_name(&self.state.players[0..self.no_of_players])
+                {
+                    ui.colored_label(
+                        Color32::LIGHT_RED,
+                        format!(
+                            "Two players are called {name}, every player needs a name of their own"
+                        ),
+                    );
                 }
-                if ui.button("Add player").clicked() && self.no_of_players < 4 {
+                if ui.button("Add player").clicked() && self.no_of_players < self.max_players() {
                     self.no_of_players += 1;
                     if self.no_of_players > self.state.players.len() {
                         self.state.players.push(Player {
                             name: String::new(),
                             hand: Hand::new(),
                             human: false,
+                            team: self.no_of_players % 2,
+                            program: None,
                         });
                     }
                 }
                 if ui.button("Remove player").clicked() && self.no_of_players > 2 {
                     self.no_of_players -= 1;
                 }
-                Ok::<bool, DurakError>(ui.button("Next").clicked())
+                let state = &mut self.state;
+                let edited = state.presets.get(&state.preset) != Some(state.rules);
+                ComboBox::from_label("Rules")
+                    .selected_text(if edited {
+                        format!("{} (edited)", state.preset)
+                    } else {
+                        state.preset.clone()
+                    })
+                    .show_ui(ui, |ui| {
+                        for name in state.presets.names() {
+                            if ui.selectable_label(state.preset == name, name).clicked() {
+                                state.preset = name.to_string();
+                                state.rules = state.presets.get(name).unwrap_or_default();
+                            }
+                        }
+                    });
+                let save_preset = ui
+                    .collapsing("House rules", |ui| {
+                        rules_editor(ui, &mut state.rules);
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.preset_name);
+                            ui.button("Save preset").clicked()
+                        })
+                        .inner
+                    })
+                    .body_returned
+                    .unwrap_or_default();
+                if let Some(message) = &self.message {
+                    ui.colored_label(Color32::LIGHT_RED, message);
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Decks");
+                    ui.radio_value(&mut self.state.decks, 1, "One");
+                    ui.radio_value(&mut self.state.decks, 2, "Two");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Cards");
+                    for (size, supported) in DeckSize::ALL.into_iter().zip(supported) {
+                        let label = format!("{} from {:?}", size.cards(), size.lowest_rank());
+                        let radio = RadioButton::new(self.state.deck_size == size, label);
+                        if ui.add_enabled(supported, radio).clicked() {
+                            self.state.deck_size = size;
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Clock");
+                    let control = &mut self.state.time_control;
+                    if ui.radio(*control == TimeControl::Off, "Off").clicked() {
+                        *control = TimeControl::Off;
+                    }
+                    let per_move = matches!(control, TimeControl::PerMove(_));
+                    if ui.radio(per_move, "Per move").clicked() && !per_move {
+                        *control = TimeControl::PerMove(DEFAULT_MOVE_TIME);
+                    }
+                    let total = matches!(control, TimeControl::Total(_));
+                    if ui.radio(total, "Total").clicked() && !total {
+                        *control = TimeControl::Total(DEFAULT_GAME_TIME);
+                    }
+                    if let TimeControl::PerMove(limit) | TimeControl::Total(limit) = control {
+                        let mut seconds = limit.as_secs();
+                        ui.add(Slider::new(&mut seconds, 5..=600).suffix(" s"));
+                        *limit = Duration::from_secs(seconds);
+                    }
+                });
+                if self.state.time_control != TimeControl::Off {
+                    ui.horizontal(|ui| {
+                        ui.label("Out of time");
+                        let action = &mut self.state.timeout_action;
+                        ui.radio_value(action, TimeoutAction::TakeOrPass, "Take or pass");
+                        ui.radio_value(action, TimeoutAction::BotMove, "Bot moves");
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Match");
+                    let goal = &mut self.state.tally.goal;
+                    if ui.radio(*goal == MatchGoal::Single, "Off").clicked() {
+                        *goal = MatchGoal::Single;
+                    }
+                    let games = matches!(goal, MatchGoal::Games(_));
+                    if ui.radio(games, "Games").clicked() && !games {
+                        *goal = MatchGoal::Games(DEFAULT_MATCH_GAMES);
+                    }
+                    let points = matches!(goal, MatchGoal::Points(_));
+                    if ui.radio(points, "Points").clicked() && !points {
+                        *goal = MatchGoal::Points(DEFAULT_MATCH_POINTS);
+                    }
+                    if let MatchGoal::Games(target) | MatchGoal::Points(target) = goal {
+                        ui.add(Slider::new(target, 1..=50));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Points per place");
+                    let scoring = &mut self.state.tally.scoring;
+                    if ui
+                        .radio(*scoring == Scoring::Ladder, "One less each")
+                        .clicked()
+                    {
+                        *scoring = Scoring::Ladder;
+                    }
+                    let custom = matches!(scoring, Scoring::Places(_));
+                    if ui.radio(custom, "Custom").clicked() && !custom {
+                        let points = (0..MAX_SEATS - 1)
+                            .map(|place| Scoring::Ladder.points(place, self.no_of_players))
+                            .collect();
+                        *scoring = Scoring::Places(points);
+                    }
+                    if let Scoring::Places(points) = scoring {
+                        // The last player left holding cards scores nothing.
+                        let places = self.no_of_players - 1;
+                        if points.len() < places {
+                            points.resize(places, 0);
+                        }
+                        for (place, points) in points.iter_mut().take(places).enumerate() {
+                            ui.add(
+                                DragValue::new(points)
+                                    .clamp_range(0..=50)
+                                    .prefix(format!("{}. ", place + 1)),
+                            );
+                        }
+                    }
+                });
+                scoreboard(ui, &self.state.tally);
+                if ui.button("New match").clicked() {
+                    self.state.tally.reset();
+                }
+                self.no_of_players = self.no_of_players.min(self.max_players());
+                let teams_possible = matches!(self.no_of_players, 4 | 6);
+                self.state.teams &= teams_possible;
+                ui.add_enabled(
+                    teams_possible,
+                    Checkbox::new(&mut self.state.teams, "Team play"),
+                );
+                ComboBox::from_label("Card theme")
+                    .selected_text(theme_label(&self.state.theme))
+                    .show_ui(ui, |ui| {
+                        for theme in &self.themes {
+                            ui.selectable_value(
+                                &mut self.state.theme,
+                                theme.clone(),
+                                theme_label(theme),
+                            );
+                        }
+                    });
+                (
+                    ui.button("Next").clicked(),
+                    ui.button("Tutorial").clicked(),
+                    ui.button("Settings").clicked(),
+                    save_preset,
+                )
             })
-            .inner?;
+            .inner;
         gui.update(ctx);
 
+        if save_preset {
+            self.message = self.save_preset(ctx).err().map(|error| error.to_string());
+        }
+        if tutorial {
+            return <Self as DurakSceneTransition<Tutorial>>::switch(self, ctx);
+        }
+        if settings {
+            return <Self as DurakSceneTransition<SettingsScreen>>::switch(self, ctx);
+        }
+
+        let players = &self.state.players[0..self.no_of_players];
+        let balanced = !self.state.teams
+            || players.iter().filter(|player| player.team == 0).count() * 2 == players.len();
         if next
-            && !self
-                .state
-                .players
-                .iter()
-                .take(self.no_of_players)
-                .any(|player| player.name.is_empty())
+            && balanced
+            && !players.iter().any(|player| player.name.is_empty())
+            && player::shared_name(players).is_none()
         {
             self.state.players.truncate(self.no_of_players);
-            let result = <Self as DurakSceneTransition<GamePlay>>::transition(self, ctx)?;
-            return Ok(Box::new(result));
+            if self.state.tally.is_over() {
+                self.state.tally.reset();
+            }
+            if self.state.teams {
+                seat_teams(&mut self.state.players);
+            }
+            return <Self as DurakSceneTransition<GamePlay>>::switch(self, ctx);
         }
 
-        Ok(self)
+        Ok(SceneSwitch::Stay(self))
     }
 
     fn draw(&self, gui: &Gui, ctx: &mut Context) -> Result<(), DurakError> {
@@ -97,26 +475,454 @@ impl Scene for MainMenu {
         Ok(())
     }
 
-    fn new(mut state: DurakState, _ctx: &Context) -> Result<MainMenu, DurakError> {
+    fn new(mut state: DurakState, ctx: &Context) -> NewScene<MainMenu> {
         for player in &mut state.players {
             player.hand.empty();
         }
         Ok(MainMenu {
             no_of_players: state.players.len(),
+            preset_name: state.preset.clone(),
             state,
+            themes: Theme::available(ctx),
+            message: None,
         })
     }
 
-    fn take_state(self) -> DurakState
-    where
-        Self: Sized,
-    {
+    fn take_state(self: Box<Self>) -> DurakState {
         self.state
     }
 }
 
+/// Pause before a bot makes its move, so the table can be followed.
+const BOT_DELAY: Duration = Duration::from_millis(700);
+
 pub struct GamePlay {
     state: DurakState,
+    seats: Vec<Seat>,
+    engine: Engine,
+    /// Index of the player at this screen.
+    local: usize,
+    bot_timer: Duration,
+    show_discarded: bool,
+    /// Why the last drop was rejected, shown until the time runs out.
+    notice: Option<(String, Duration)>,
+    /// Bots wait and cards can't be moved while the pause overlay is open.
+    paused: bool,
+    /// Undone moves, the next one to redo last.
+    redo: Vec<(usize, Action)>,
+    clocks: Clocks,
+    /// Shuffles the deck and makes the random choices of the bots.
+    rng: StdRng,
+    /// Programs playing the bots that have one, by seat.
+    external: Vec<Option<ExternalBot>>,
+    /// Moves of a saved game still to be played back, the next one last.
+    /// Nobody else moves until they are all made.
+    playback: Vec<(usize, Action)>,
+    /// Seat of the player who joined over the network, played through
+    /// `external`.
+    guest: Option<usize>,
+}
+
+/// A game ready to be laid out on the table.
+struct Deal {
+    deck: Deck,
+    engine: Engine,
+    /// Cards drawn while dealing, for the sprites to follow.
+    events: Vec<Event>,
+    rng: StdRng,
+    /// Moves of a saved game to play back, the next one last.
+    playback: Vec<(usize, Action)>,
+}
+
+/// How long the reason for a rejected drop stays on screen.
+const NOTICE_TIME: Duration = Duration::from_secs(2);
+
+impl GamePlay {
+    /// Applies `action` for `player` and moves the card sprites to match.
+    fn play(&mut self, player: usize, action: Action) -> Result<(), DurakError> {
+        let events = self.engine.apply(player, action)?;
+        self.clocks.moved();
+        if player == self.local {
+            self.redo.clear();
+        }
+        self.replay(events)
+    }
+
+    /// Undo is only offered while everyone else at the table is a bot, so
+    /// nobody else's moves are taken back, and outside of matches, whose
+    /// scores would mean nothing if moves could be taken back.
+    fn undo_allowed(&self) -> bool {
+        self.state.tally.goal == MatchGoal::Single
+            && self.playback.is_empty()
+            && self.guest.is_none()
+            && self
+                .state
+                .players
+                .iter()
+                .filter(|player| player.human)
+                .count()
+                == 1
+    }
+
+    /// Takes back the local player's last move and every bot move since,
+    /// along with the time spent on them.
+    fn undo(&mut self) -> Result<(), DurakError> {
+        let log = self.engine.log();
+        let Some(last) = log.iter().rposition(|(player, _)| *player == self.local) else {
+            return Ok(());
+        };
+        self.redo.extend(log[last..].iter().rev());
+        self.engine = self.engine.rewind(last)?;
+        self.bot_timer = Duration::ZERO;
+        self.clocks.rewind(last);
+        self.restore_layout()
+    }
+
+    /// Plays the last undone move again along with the bot replies that
+    /// followed it, as long as they are still legal.
+    fn redo(&mut self) -> Result<(), DurakError> {
+        while let Some((player, action)) = self.redo.pop() {
+            if self.engine.check(player, action).is_err() {
+                self.redo.clear();
+                break;
+            }
+            let events = self.engine.apply(player, action)?;
+            self.clocks.moved();
+            self.replay(events)?;
+            if self
+                .redo
+                .last()
+                .is_some_and(|(player, _)| *player == self.local)
+            {
+                break;
+            }
+        }
+        self.bot_timer = Duration::ZERO;
+        Ok(())
+    }
+
+    /// Moves every card sprite to where the engine says the card is.
+    fn restore_layout(&mut self) -> Result<(), DurakError> {
+        let mut cards: Vec<Card> = Vec::new();
+        for player in &mut self.state.players {
+            cards.extend(player.hand.take_all());
+        }
+        if let Some(mat) = self.state.mat.as_mut() {
+            cards.extend(mat.take_cards());
+        }
+        if let Some(deck) = self.state.deck.as_mut() {
+            cards.extend(deck.take_all());
+        }
+        cards.extend(self.state.discard_pile.take_all());
+        cards.extend(self.state.held_card.take());
+
+        let mut pool: HashMap<CardId, Card> =
+            cards.into_iter().map(|card| (card.id(), card)).collect();
+        let mut take = |id: CardId| {
+            pool.remove(&id).ok_or_else(|| {
+                DurakError::state(format!("{:?} of {:?} went missing", id.rank, id.suit))
+            })
+        };
+
+        let (Some(deck), Some(mat)) = (self.state.deck.as_mut(), self.state.mat.as_mut()) else {
+            return Err(DurakError::state("no table to play on"));
+        };
+        deck.restore(
+            self.engine
+                .talon()
+                .iter()
+                .map(|id| take(*id))
+                .collect::<Result<Cards, _>>()?,
+        );
+        for (i, player) in self.state.players.iter_mut().enumerate() {
+            for id in self.engine.hand(i) {
+                player.push_card(take(*id)?);
+            }
+        }
+        for (stack, (attack, cover)) in self.engine.table().iter().enumerate() {
+            let mut card = take(*attack)?;
+            card.flip(true);
+            if mat.attack(card).is_some() {
+                return Err(DurakError::state("the table refused an attack"));
+            }
+            if let Some(cover) = cover {
+                let mut card = take(*cover)?;
+                card.flip(true);
+                if mat.defend(stack, card, self.engine.trump()).is_some() {
+                    return Err(DurakError::state("the table refused a defence"));
+                }
+            }
+        }
+        for id in self.engine.discard() {
+            self.state.discard_pile.push(take(*id)?);
+        }
+        self.state.discard_pile.settle();
+
+        Ok(())
+    }
+
+    fn replay(&mut self, events: Vec<Event>) -> Result<(), DurakError> {
+        let trump = self.engine.trump();
+        let (Some(deck), Some(mat)) = (self.state.deck.as_mut(), self.state.mat.as_mut()) else {
+            return Err(DurakError::state("no table to play on"));
+        };
+        let players = &mut self.state.players;
+        let missing =
+            |id: CardId| DurakError::state(format!("{:?} of {:?} went missing", id.rank, id.suit));
+
+        for event in events {
+            match event {
+                Event::Attacked(player, id) | Event::Transferred(player, id) => {
+                    let mut card = players[player]
+                        .hand
+                        .take_card(id)
+                        .ok_or_else(|| missing(id))?;
+                    card.flip(true);
+                    if mat.attack(card).is_some() {
+                        return Err(DurakError::state("the table refused an attack"));
+                    }
+                }
+                Event::Defended(player, stack, id) => {
+                    let mut card = players[player]
+                        .hand
+                        .take_card(id)
+                        .ok_or_else(|| missing(id))?;
+                    card.flip(true);
+                    if mat.defend(stack, card, trump).is_some() {
+                        return Err(DurakError::state("the table refused a defence"));
+                    }
+                }
+                Event::Took(player) => {
+                    for card in mat.take_cards() {
+                        players[player].push_card(card);
+                    }
+                }
+                Event::Beaten => {
+                    for card in mat.take_cards() {
+                        self.state.discard_pile.push(card);
+                    }
+                }
+                Event::Drew(player, id) => {
+                    let card = deck.take(id).ok_or_else(|| missing(id))?;
+                    players[player].push_card(card);
+                }
+                Event::Shown(player, id) => {
+                    let name = &players[player].name;
+                    self.notice = Some((format!("{name} shows {id}"), NOTICE_TIME));
+                }
+                Event::Out(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the clocks and moves for the players who ran out of time.
+    fn run_clocks(&mut self, delta: Duration) -> Result<(), DurakError> {
+        let engine = &self.engine;
+        let late = self
+            .clocks
+            .tick(delta, |player| engine.needs_to_act(player));
+        // One move at a time, since it changes who has to act next.
+        let timeout = late.into_iter().find_map(|player| {
+            let action = self.state.timeout_action.action(&self.engine, player)?;
+            Some((player, action))
+        });
+        if let Some((player, action)) = timeout {
+            self.play(player, action)?;
+        }
+        Ok(())
+    }
+
+    /// Lets the first bot that has to act make its move. A bot played by a
+    /// program is waited for, and the built-in bot moves for it when the
+    /// program fails. While a saved game is played back its next move is
+    /// made instead, whoever's it is.
+    fn step_bots(&mut self) -> Result<(), DurakError> {
+        if let Some((player, action)) = self.playback.pop() {
+            self.bot_timer = Duration::ZERO;
+            return self.play(player, action);
+        }
+        let bot = (0..self.engine.players())
+            .find(|player| !self.state.players[*player].human && self.engine.needs_to_act(*player));
+        let Some(player) = bot else {
+            return Ok(());
+        };
+        let polled = self.external[player]
+            .as_mut()
+            .map(|program| program.poll(&self.engine, player));
+        let action = match polled {
+            Some(Ok(None)) => return Ok(()),
+            Some(Ok(Some(action))) => Some(action),
+            Some(Err(error)) => {
+                let name = &self.state.players[player].name;
+                self.notice = Some((format!("{name}: {error}"), NOTICE_TIME));
+                None
+            }
+            None => None,
+        };
+        let difficulty = self.state.settings.bot_difficulty;
+        let action = action.or_else(|| bot::play(&self.engine, player, difficulty, &mut self.rng));
+        if let Some(action) = action {
+            self.bot_timer = Duration::ZERO;
+            self.play(player, action)?;
+        }
+        Ok(())
+    }
+
+    /// Acts on the take, pass, undo or redo button pressed this frame, then
+    /// moves the animations, clocks and bots on by `delta`.
+    fn advance(&mut self, buttons: [bool; 4], delta: Duration) -> Result<(), DurakError> {
+        let [take, pass, undo, redo] = buttons;
+        if take {
+            self.play(self.local, Action::Take)?;
+        } else if pass {
+            self.play(self.local, Action::Pass)?;
+        } else if undo {
+            self.undo()?;
+        } else if redo {
+            self.redo()?;
+        }
+
+        let speed = self.state.settings.animation_speed;
+        self.state.discard_pile.update(delta.mul_f32(speed));
+        if self.playback.is_empty() {
+            self.run_clocks(delta)?;
+        }
+        self.bot_timer += delta.mul_f32(speed);
+        if self.bot_timer >= BOT_DELAY {
+            self.step_bots()?;
+        }
+        Ok(())
+    }
+
+    /// Shuffles a deck of the chosen theme and deals it out.
+    /// Deals a new game, or the saved game to play back along with the moves
+    /// made in it.
+    fn deal(state: &mut DurakState, ctx: &Context) -> Result<Deal, DurakError> {
+        let theme = state.assets.load::<Theme>(ctx, &state.theme)?;
+        let theme = state
+            .assets
+            .get(theme)
+            .ok_or_else(|| DurakError::asset(&state.theme))?;
+        let mut deck = Deck::new(theme, state.deck_size, state.decks)?;
+        let mut rng = match state.seed {
+            // Every game of a match gets its own deal.
+            Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(u64::from(state.times_played))),
+            None => StdRng::from_entropy(),
+        };
+        if let Some(replay) = state.replay.take() {
+            deck.arrange(&replay.deal)?;
+            // Checks every move before any is played back.
+            let game = replay.game()?;
+            let (engine, events) = replay.start()?;
+            let playback = game.log().iter().rev().copied().collect();
+            return Ok(Deal {
+                deck,
+                engine,
+                events,
+                rng,
+                playback,
+            });
+        }
+        deck.shuffle(&mut rng);
+
+        let talon = deck.cards().iter().map(Card::id).collect();
+        let teams = state
+            .teams
+            .then(|| state.players.iter().map(|player| player.team).collect());
+        let (mut engine, events) = Engine::new(talon, state.players.len(), teams, state.rules)?;
+        if state.tally.goal != MatchGoal::Single {
+            // House rule: the loser of the last game attacks first.
+            let loser = state
+                .tally
+                .last_loser()
+                .and_then(|name| state.players.iter().position(|player| player.name == name));
+            if let Some(loser) = loser {
+                engine.set_leader(loser);
+            }
+        }
+        Ok(Deal {
+            deck,
+            engine,
+            events,
+            rng,
+            playback: Vec::new(),
+        })
+    }
+
+    /// Keeps the game so far as the last one played, to be played back with
+    /// `--replay`.
+    fn save_replay(&self, ctx: &Context) {
+        let names = self
+            .state
+            .players
+            .iter()
+            .map(|player| player.name.clone())
+            .collect();
+        let replay = Replay::record(names, &self.engine, self.state.deck_size, self.state.decks);
+        if let Err(error) = replay.save_last(ctx) {
+            error.report(ctx);
+        }
+    }
+
+    /// Marks the move the advisor suggests when hints are on and returns the
+    /// reason for it.
+    fn update_hint(&mut self) -> Option<&'static str> {
+        let advice = self
+            .state
+            .hints
+            .then(|| bot::advise(&self.engine, self.local))
+            .flatten();
+        let action = advice.map(|(action, _)| action);
+        self.state.players[self.local]
+            .hand
+            .set_hint(action.and_then(Action::card));
+        if let Some(mat) = self.state.mat.as_mut() {
+            mat.set_hint(match action {
+                Some(Action::Defend(stack, _)) => Some(stack),
+                _ => None,
+            });
+        }
+        advice.map(|(_, reason)| reason)
+    }
+
+    /// Dims the cards the local player can't play and shows where the held
+    /// card can go.
+    fn update_legal_moves(&mut self) {
+        let legal = self.engine.legal_actions(self.local);
+        let playable: Vec<CardId> = legal.iter().filter_map(|action| action.card()).collect();
+        self.state.players[self.local].hand.dim_except(&playable);
+
+        let targets = self.state.held_card.as_mut().map(|card| {
+            let id = card.id();
+            card.set_dimmed(!playable.contains(&id));
+            let stacks = legal
+                .iter()
+                .filter_map(|action| match action {
+                    Action::Defend(stack, card) if *card == id => Some(*stack),
+                    _ => None,
+                })
+                .collect();
+            (playable.contains(&id), stacks)
+        });
+        if let Some(mat) = self.state.mat.as_mut() {
+            mat.set_drop_targets(targets);
+        }
+    }
+
+    fn status(&self) -> String {
+        let name = |player: usize| &self.state.players[player].name;
+        let (attacker, defender) = (self.engine.attacker(), self.engine.defender());
+        if !self.playback.is_empty() {
+            format!("Playing back, {} moves to go", self.playback.len())
+        } else if self.engine.needs_to_act(self.local) {
+            String::from("Your move")
+        } else if self.engine.taking() {
+            format!("{} is taking", name(defender))
+        } else {
+            format!("{} attacks {}", name(attacker), name(defender))
+        }
+    }
 }
 
 impl Scene for GamePlay {
@@ -124,35 +930,134 @@ impl Scene for GamePlay {
 
     type Error = DurakError;
     fn update(mut self: Box<Self>, gui: &mut Gui, ctx: &mut Context) -> SceneResult<Self> {
-        let (next, pop_card) = Area::new("id")
+        let playing_back = !self.playback.is_empty();
+        let can_take = !playing_back && self.engine.check(self.local, Action::Take).is_ok();
+        let can_pass = !playing_back && self.engine.check(self.local, Action::Pass).is_ok();
+        let status = self.status();
+        let advice = self.update_hint();
+        let can_undo = self.undo_allowed()
+            && self
+                .engine
+                .log()
+                .iter()
+                .any(|(player, _)| *player == self.local);
+        let can_redo = self.undo_allowed() && !self.redo.is_empty();
+        let (take, pass, undo, redo) = Area::new("id")
             .show(&gui.ctx(), |ui| {
                 ui.label(format!("{} times played", &self.state.times_played));
-                (ui.button("Next").clicked(), ui.button("pop").clicked())
+                ui.label(status);
+                if let Some((reason, _)) = &self.notice {
+                    ui.colored_label(Color32::LIGHT_RED, reason);
+                }
+                if let Some(reason) = advice {
+                    ui.colored_label(Color32::LIGHT_BLUE, format!("Hint: {reason}"));
+                }
+                if ui.button("Pause").clicked() {
+                    self.paused = true;
+                }
+                ui.horizontal(|ui| {
+                    (
+                        ui.add_enabled(can_take, Button::new("Take")).clicked(),
+                        ui.add_enabled(can_pass, Button::new("Pass")).clicked(),
+                        ui.add_enabled(can_undo, Button::new("Undo")).clicked(),
+                        ui.add_enabled(can_redo, Button::new("Redo")).clicked(),
+                    )
+                })
+                .inner
             })
             .inner;
+        let mut menu = false;
+        if self.paused {
+            Window::new("Paused")
+                .resizable(false)
+                .collapsible(false)
+                .show(&gui.ctx(), |ui| {
+                    ui.checkbox(&mut self.state.hints, "Show hints");
+                    ui.checkbox(&mut self.show_discarded, "Show discarded cards");
+                    if ui.button("Resume").clicked() {
+                        self.paused = false;
+                    }
+                    menu = ui.button("Main Menu").clicked();
+                });
+        }
+        if self.show_discarded {
+            let pile = &self.state.discard_pile;
+            Window::new(format!("Discarded ({})", pile.len()))
+                .resizable(false)
+                .show(&gui.ctx(), |ui| {
+                    for suit in Suit::ALL {
+                        let ranks: Vec<&str> = pile
+                            .of_suit(suit)
+                            .iter()
+                            .map(|card| card.rank().label())
+                            .collect();
+                        ui.label(format!("{} {}", suit.symbol(), ranks.join(" ")));
+                    }
+                });
+        }
         gui.update(ctx);
-        if pop_card {
-            if let Some(card) = self.state.deck.as_mut().unwrap().pop() {
-                self.state.players[0].push_card(card);
-            }
+
+        if menu {
+            self.save_replay(ctx);
+            return <Self as DurakSceneTransition<MainMenu>>::switch(self, ctx);
+        }
+        if self.paused {
+            return Ok(SceneSwitch::Stay(self));
         }
 
+        if let Err(error) = self.advance([take, pass, undo, redo], ctx.time.delta()) {
+            return Ok(SceneSwitch::Failed(self.take_state(), error));
+        }
+
+        let delta = ctx.time.delta();
+        self.notice = self
+            .notice
+            .take()
+            .and_then(|(reason, left)| Some((reason, left.checked_sub(delta)?)));
+
+        self.update_legal_moves();
         if let Some(mat) = self.state.mat.as_mut() {
             mat.update_intersect(ctx.mouse.position().into())
         }
 
         if self.state.held_card.is_none() {
-            self.state.players[0]
+            self.state.players[self.local]
                 .hand
                 .update_hover(ctx.mouse.position().into());
         }
 
-        if next {
+        if let Some(outcome) = self.engine.outcome() {
+            self.save_replay(ctx);
+            self.state.outcome = Some(outcome);
             self.state.times_played += 1;
-            let result = <Self as DurakSceneTransition<GameOver>>::transition(self, ctx)?;
-            return Ok(Box::new(result));
+            let names: Vec<&str> = self
+                .state
+                .players
+                .iter()
+                .map(|player| player.name.as_str())
+                .collect();
+            let finished = self.engine.finished();
+            if self.state.teams {
+                let losing_team = match outcome {
+                    Outcome::LosingTeam(team) => Some(team),
+                    _ => None,
+                };
+                let loser = (0..names.len())
+                    .find(|player| !finished.contains(player))
+                    .map(|player| names[player]);
+                self.state
+                    .tally
+                    .record_teams(&TEAM_NAMES, losing_team, loser);
+            } else {
+                self.state.tally.record(&names, finished);
+            }
+            let text = result(&self.state);
+            for program in self.external.iter_mut().flatten() {
+                program.finish(&text);
+            }
+            return <Self as DurakSceneTransition<GameOver>>::switch(self, ctx);
         }
-        Ok(self)
+        Ok(SceneSwitch::Stay(self))
     }
 
     fn draw(&self, gui: &Gui, ctx: &mut Context) -> Result<(), DurakError> {
@@ -160,15 +1065,19 @@ impl Scene for GamePlay {
         if let Some(mat) = &self.state.mat {
             mat.draw(&mut canvas, ctx)?;
         }
-        for player in self.state.players.iter() {
-            let circle = Mesh::new_circle(ctx, DrawMode::fill(), Vec2::ZERO, 5., 1., Color::RED)?;
-
+        for (i, (player, seat)) in self.state.players.iter().zip(&self.seats).enumerate() {
             player.hand.draw(&mut canvas, ctx)?;
-            canvas.draw(&circle, DrawParam::new().dest(player.hand.get_pos()));
+            let active = self.engine.needs_to_act(i);
+            seat.draw_label(&mut canvas, ctx, &player.name, player.hand.len(), active)?;
+            if let Some(remaining) = self.clocks.remaining(i) {
+                seat.draw_clock(&mut canvas, remaining, active);
+            }
         }
 
+        self.state.discard_pile.draw(&mut canvas)?;
+
         if let Some(deck) = &self.state.deck {
-            deck.draw(&mut canvas, DrawParam::new())?;
+            deck.draw(&mut canvas, ctx)?;
         }
 
         if let Some(card) = &self.state.held_card {
@@ -202,78 +1111,241 @@ impl Scene for GamePlay {
         _y: f32,
         _ctx: &Context,
     ) -> Result<(), Self::Error> {
-        if self.state.held_card.is_none() {
-            self.state.held_card = self.state.players[0].hand.take_hovered();
+        if self.state.held_card.is_none() && !self.paused && self.playback.is_empty() {
+            self.state.held_card = self.state.players[self.local].hand.take_hovered();
         }
         Ok(())
     }
 
-    fn mouse_button_up_event(
-        &mut self,
-        _x: f32,
-        _y: f32,
-        _ctx: &Context,
-    ) -> Result<(), Self::Error> {
-        if let Some(mat) = self.state.mat.as_mut() {
-            if let Some(card) = self.state.held_card.take() {
-                if mat.intersect() {
-                    if let Some(card) = mat.attack(card) {
-                    self.state.players[0].hand.put_back(card)
+    fn mouse_button_up_event(&mut self, x: f32, y: f32, _ctx: &Context) -> Result<(), Self::Error> {
+        let Some(card) = self.state.held_card.take() else {
+            return Ok(());
+        };
+        let id = card.id();
+        let hand = &mut self.state.players[self.local].hand;
+        hand.put_back(card);
+        let Some(mat) = self.state.mat.as_ref().filter(|mat| mat.intersect()) else {
+            return Ok(());
+        };
+        hand.remove_hover();
 
-                    }
-                    self.state.players[0].hand.remove_hover();
-                } else {
-                    self.state.players[0].hand.put_back(card)
-                }
+        let action = self
+            .engine
+            .play_card(self.local, id, mat.stack_at(vec2(x, y)));
+
+        match self.play(self.local, action) {
+            // An illegal card stays in the hand.
+            Err(DurakError::Rules(reason)) => {
+                self.notice = Some((reason, NOTICE_TIME));
+                Ok(())
             }
+            result => result,
         }
-        Ok(())
     }
 
-    fn new(mut state: DurakState, ctx: &Context) -> Result<GamePlay, DurakError> {
-        let image = storage::card_image()?.ok_or("Cannot load card image")?;
-
-        let mut deck = Deck::new(&image)?;
-        deck.shuffle();
+    fn new(mut state: DurakState, ctx: &Context) -> NewScene<GamePlay> {
+        let Deal {
+            deck,
+            engine,
+            events,
+            rng,
+            playback,
+        } = match GamePlay::deal(&mut state, ctx) {
+            Ok(deal) => deal,
+            Err(error) => return Err((error, state)),
+        };
 
         let PhysicalSize { height, width, .. } = ctx.gfx.window().inner_size();
         let table_size = (height.min(width) / 2) as f32;
-        
+
         state.mat = Some(Mat::new(Rect::new(
             width as f32 - table_size,
             0.,
             table_size,
             height as f32,
         )));
+        state.discard_pile = DiscardPile::new(vec2(width as f32 - CARD_WIDTH, height as f32 - 40.));
 
-        let rotation_step = (360. / state.players.len() as f32).to_radians();
+        let local = state
+            .players
+            .iter()
+            .position(|player| player.human)
+            .unwrap_or_default();
+        let seats = Seat::layout(
+            state.players.len(),
+            local,
+            vec2(table_size, table_size),
+            table_size * 3. / 4.,
+        );
 
-        for (i, player) in state.players.iter_mut().enumerate() {
-            let rotation = rotation_step * i as f32;
-            let radius = vec2(0., table_size * 3. / 4.);
-            let center = vec2(table_size, table_size);
-            let pos = center + Vec2::from_angle(rotation).rotate(radius);
+        for (player, seat) in state.players.iter_mut().zip(&seats) {
+            player.hand.set_pos(seat.pos);
+            player.hand.set_rotation(seat.rotation);
+            player.hand.set_compact(!player.human);
+        }
 
-            player.hand.set_pos(pos);
-            player.hand.set_rotation(rotation);
+        state.deck = Some(deck);
+        state.outcome = None;
+        let clocks = Clocks::new(state.time_control, state.players.len());
+        let mut notice = None;
+        let mut external: Vec<Option<ExternalBot>> = state
+            .players
+            .iter()
+            .map(|player| {
+                let command = player.program.as_ref().filter(|_| !player.human)?;
+                ExternalBot::spawn(command, MOVE_TIMEOUT)
+                    .map_err(|error| {
+                        notice = Some((format!("{}: {error}", player.name), NOTICE_TIME));
+                    })
+                    .ok()
+            })
+            .collect();
+        let guest = state.guest.take().and_then(|(seat, guest)| {
+            *external.get_mut(seat)? = Some(guest);
+            Some(seat)
+        });
+        let mut result = GamePlay {
+            state,
+            seats,
+            engine,
+            local,
+            bot_timer: Duration::ZERO,
+            show_discarded: false,
+            notice,
+            paused: false,
+            redo: Vec::new(),
+            clocks,
+            rng,
+            external,
+            playback,
+            guest,
+        };
+        match result.replay(events) {
+            Ok(()) => Ok(result),
+            Err(error) => Err((error, Box::new(result).take_state())),
         }
+    }
+
+    /// Gives back the player who joined over the network along with the
+    /// state, for the next game.
+    fn take_state(mut self: Box<Self>) -> DurakState {
+        if let Some(seat) = self.guest {
+            self.state.guest = self.external[seat].take().map(|guest| (seat, guest));
+        }
+        self.state
+    }
+}
+
+/// Edits the preferences kept in the settings file.
+pub struct SettingsScreen {
+    state: DurakState,
+    settings: Settings,
+    themes: Vec<String>,
+    message: Option<String>,
+}
+
+impl Scene for SettingsScreen {
+    type State = DurakState;
+    type Error = DurakError;
+
+    fn update(mut self: Box<Self>, gui: &mut Gui, ctx: &mut Context) -> SceneResult<Self> {
+        let (save, back) = Area::new("id")
+            .show(&gui.ctx(), |ui| {
+                let settings = &mut self.settings;
+                ui.label("Settings");
+                ui.horizontal(|ui| {
+                    ui.label("Your name");
+                    ui.text_edit_singleline(&mut settings.player_name);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Window");
+                    ui.add(Slider::new(&mut settings.window_width, WINDOW_WIDTHS).suffix(" px"));
+                    ui.add(Slider::new(&mut settings.window_height, WINDOW_HEIGHTS).suffix(" px"));
+                });
+                ui.checkbox(&mut settings.fullscreen, "Fullscreen");
+                ui.add(
+                    Slider::new(&mut settings.animation_speed, ANIMATION_SPEEDS)
+                        .text("Animation speed"),
+                );
+                ComboBox::from_label("Card theme")
+                    .selected_text(theme_label(&settings.theme))
+                    .show_ui(ui, |ui| {
+                        for theme in &self.themes {
+                            ui.selectable_value(
+                                &mut settings.theme,
+                                theme.clone(),
+                                theme_label(theme),
+                            );
+                        }
+                    });
+                ComboBox::from_label("Rules")
+                    .selected_text(&settings.preset)
+                    .show_ui(ui, |ui| {
+                        for name in self.state.presets.names() {
+                            ui.selectable_value(&mut settings.preset, name.to_string(), name);
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label("Bots");
+                    let difficulty = &mut settings.bot_difficulty;
+                    ui.radio_value(difficulty, Difficulty::Easy, "Easy");
+                    ui.radio_value(difficulty, Difficulty::Normal, "Normal");
+                    ui.radio_value(difficulty, Difficulty::Hard, "Hard");
+                });
+                if let Some(message) = &self.message {
+                    ui.colored_label(Color32::LIGHT_RED, message);
+                }
+                ui.horizontal(|ui| (ui.button("Save").clicked(), ui.button("Back").clicked()))
+                    .inner
+            })
+            .inner;
+        gui.update(ctx);
 
-        for _ in 0..7 {
-            for player in &mut state.players {
-                let card = deck.pop().ok_or(DurakError::from("Insufficient Cards"))?;
-                player.push_card(card);
+        if save {
+            match self.settings.save(ctx) {
+                Ok(()) => self.message = None,
+                Err(error) => self.message = Some(error.to_string()),
             }
+            if let Err(error) = self.settings.apply_window(ctx) {
+                return Ok(SceneSwitch::Failed(self.take_state(), error));
+            }
+            let settings = self.settings.clone();
+            self.state.theme = settings.theme.clone();
+            if let Some(rules) = self.state.presets.get(&settings.preset) {
+                self.state.preset = settings.preset.clone();
+                self.state.rules = rules;
+            }
+            if let Some(player) = self.state.players.iter_mut().find(|player| player.human) {
+                player.name = settings.player_name.clone();
+            }
+            self.state.settings = settings;
+        }
+        if back {
+            return <Self as DurakSceneTransition<MainMenu>>::switch(self, ctx);
         }
 
-        state.deck = Some(deck);
-        let result = GamePlay { state };
-        Ok(result)
+        Ok(SceneSwitch::Stay(self))
     }
 
-    fn take_state(self) -> DurakState
-    where
-        Self: Sized,
-    {
+    fn draw(&self, gui: &Gui, ctx: &mut Context) -> Result<(), DurakError> {
+        let mut canvas = Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+
+        gui.draw(&mut canvas, DrawParam::new());
+        canvas.finish(ctx)?;
+
+        Ok(())
+    }
+
+    fn new(state: DurakState, ctx: &Context) -> NewScene<SettingsScreen> {
+        Ok(SettingsScreen {
+            settings: state.settings.clone(),
+            state,
+            themes: Theme::available(ctx),
+            message: None,
+        })
+    }
+
+    fn take_state(self: Box<Self>) -> DurakState {
         self.state
     }
 }
@@ -282,6 +1354,24 @@ pub struct GameOver {
     state: DurakState,
 }
 
+/// Who lost the game that just ended.
+fn result(state: &DurakState) -> String {
+    let players = &state.players;
+    match state.outcome {
+        Some(Outcome::Durak(player)) => format!("{} is the durak", players[player].name),
+        Some(Outcome::LosingTeam(team)) => {
+            let members: Vec<&str> = players
+                .iter()
+                .filter(|player| player.team == team)
+                .map(|player| player.name.as_str())
+                .collect();
+            format!("{} loses ({})", TEAM_NAMES[team], members.join(", "))
+        }
+        Some(Outcome::Draw) => String::from("Draw"),
+        None => String::from("Game abandoned"),
+    }
+}
+
 impl Scene for GameOver {
     type State = DurakState;
 
@@ -290,17 +1380,18 @@ impl Scene for GameOver {
         let next = Area::new("id")
             .show(&gui.ctx(), |ui| {
                 ui.label("Game Over");
+                ui.label(result(&self.state));
                 ui.label(format!("{} times played", &self.state.times_played));
+                scoreboard(ui, &self.state.tally);
                 ui.button("Next").clicked()
             })
             .inner;
         gui.update(ctx);
         if next {
-            let result = <Self as DurakSceneTransition<MainMenu>>::transition(self, ctx)?;
-            return Ok(Box::new(result));
+            return <Self as DurakSceneTransition<MainMenu>>::switch(self, ctx);
         }
 
-        Ok(self)
+        Ok(SceneSwitch::Stay(self))
     }
 
     fn draw(&self, gui: &Gui, ctx: &mut Context) -> Result<(), DurakError> {
@@ -312,17 +1403,92 @@ impl Scene for GameOver {
         Ok(())
     }
 
-    fn new(mut state: DurakState, _ctx: &Context) -> Result<GameOver, DurakError> {
+    fn new(mut state: DurakState, _ctx: &Context) -> NewScene<GameOver> {
         state.deck = None;
         state.mat = None;
-        state.discard_pile = Vec::new();
+        state.discard_pile.empty();
         Ok(GameOver { state })
     }
 
-    fn take_state(self) -> DurakState
-    where
-        Self: Sized,
-    {
+    fn take_state(self: Box<Self>) -> DurakState {
+        self.state
+    }
+}
+
+/// Shown in place of the current scene when it fails with a recoverable error.
+pub struct ErrorScreen {
+    state: DurakState,
+    message: String,
+    /// The state went down with the failed scene and was rebuilt from the
+    /// saved settings.
+    reset: bool,
+}
+
+impl ErrorScreen {
+    /// Shows `error`, carrying on with the players, rules and match of
+    /// `state`. The game that was going on is cleared away.
+    pub fn with_error(mut state: DurakState, error: &DurakError, reset: bool) -> Self {
+        state.deck = None;
+        state.mat = None;
+        state.discard_pile.empty();
+        state.held_card = None;
+        state.outcome = None;
+        for player in &mut state.players {
+            player.hand.empty();
+        }
+        ErrorScreen {
+            state,
+            message: error.to_string(),
+            reset,
+        }
+    }
+}
+
+impl Scene for ErrorScreen {
+    type State = DurakState;
+
+    type Error = DurakError;
+    fn update(self: Box<Self>, gui: &mut Gui, ctx: &mut Context) -> SceneResult<Self> {
+        let (new_game, menu) = Area::new("id")
+            .show(&gui.ctx(), |ui| {
+                ui.label("Something went wrong");
+                ui.label(&self.message);
+                ui.label("Details were written to error.log");
+                if self.reset {
+                    ui.label("The game setup was reset to your saved settings.");
+                }
+                (
+                    ui.button("New game").clicked(),
+                    ui.button("Main Menu").clicked(),
+                )
+            })
+            .inner;
+        gui.update(ctx);
+        if new_game {
+            return <Self as DurakSceneTransition<GamePlay>>::switch(self, ctx);
+        }
+        if menu {
+            return <Self as DurakSceneTransition<MainMenu>>::switch(self, ctx);
+        }
+
+        Ok(SceneSwitch::Stay(self))
+    }
+
+    fn draw(&self, gui: &Gui, ctx: &mut Context) -> Result<(), DurakError> {
+        let mut canvas = Canvas::from_frame(ctx, Color::from([0.3, 0.1, 0.1, 1.0]));
+
+        gui.draw(&mut canvas, DrawParam::new());
+        canvas.finish(ctx)?;
+
+        Ok(())
+    }
+
+    fn new(state: DurakState, _ctx: &Context) -> NewScene<ErrorScreen> {
+        let error = DurakError::state("unknown error");
+        Ok(ErrorScreen::with_error(state, &error, false))
+    }
+
+    fn take_state(self: Box<Self>) -> DurakState {
         self.state
     }
 }
@@ -6,7 +6,15 @@ use ggez::{
     Context,
 };
 
-use crate::{card::Card, cards::Cards, error::DurakError};
+use crate::{card::Card, cards::Cards, error::DurakError, rules::CardId};
+
+/// Outline of the card and stack suggested by the advisor.
+pub const HINT_COLOR: Color = Color::new(0.3, 0.8, 1., 0.9);
+
+/// Size of the cards in a compact hand relative to a full size card.
+const COMPACT_SCALE: f32 = 0.6;
+/// Distance between neighbouring cards in a compact hand.
+const COMPACT_SPACING: f32 = 10.;
 
 #[derive(Debug, Default)]
 pub struct Hand {
@@ -14,6 +22,10 @@ pub struct Hand {
     pos: Vec2,
     rotation: f32,
     hovered: Option<usize>,
+    /// Card suggested by the advisor.
+    hint: Option<CardId>,
+    /// Opponents' hands are drawn as a small overlapping row instead of a fan.
+    compact: bool,
 }
 
 impl Hand {
@@ -23,6 +35,8 @@ impl Hand {
             pos: Vec2::ZERO,
             rotation: 0.,
             hovered: None,
+            hint: None,
+            compact: false,
         }
     }
 
@@ -38,6 +52,15 @@ impl Hand {
         self.rotation = rotation
     }
 
+    pub fn set_compact(&mut self, compact: bool) {
+        self.compact = compact;
+        self.set_card_params();
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
     pub fn take_hovered(&mut self) -> Option<Card> {
         if let Some(idx) = self.hovered {
             self.cards.take(idx)
@@ -74,6 +97,22 @@ impl Hand {
         }
     }
 
+    pub fn take_card(&mut self, id: CardId) -> Option<Card> {
+        let idx = self.cards.iter().position(|card| card.id() == id)?;
+        self.take(idx)
+    }
+
+    pub fn set_hint(&mut self, hint: Option<CardId>) {
+        self.hint = hint;
+    }
+
+    /// Dims every card not in `playable`.
+    pub fn dim_except(&mut self, playable: &[CardId]) {
+        for card in self.cards.iter_mut() {
+            card.set_dimmed(!playable.contains(&card.id()));
+        }
+    }
+
     pub fn update_hover(&mut self, mouse_pos: Vec2) {
         self.hovered = self.cards.iter().enumerate().rev().find_map(|(i, card)| {
             if card.intersect(mouse_pos) {
@@ -89,6 +128,12 @@ impl Hand {
         self.set_card_params();
     }
 
+    /// Takes every card out of the hand, e.g. to lay the table out again.
+    pub fn take_all(&mut self) -> Cards {
+        self.hovered = None;
+        std::mem::replace(&mut self.cards, Cards::new())
+    }
+
     pub fn empty(&mut self) {
         self.cards = Cards::new();
     }
@@ -99,7 +144,18 @@ impl Hand {
 
         let cards_len = self.cards.len() as f32;
 
-        let total_angle = (8. * cards_len).min(90.).max(45.).to_radians();
+        if self.compact {
+            let step = Vec2::from_angle(rotation).rotate(vec2(COMPACT_SPACING, 0.));
+            let start = pos - step * (cards_len - 1.) / 2.;
+            for (i, card) in self.cards.iter_mut().enumerate() {
+                card.set_pos(start + step * i as f32);
+                card.set_rotation(rotation);
+                card.set_scale(COMPACT_SCALE);
+            }
+            return;
+        }
+
+        let total_angle = (8. * cards_len).clamp(45., 90.).to_radians();
         let step_angle = total_angle / cards_len;
         let radius = vec2(0., 0. - (180_f32.to_radians() * 7.) / (PI * step_angle));
         let rotation_vec = Vec2::from_angle(rotation);
@@ -113,6 +169,7 @@ impl Hand {
         }) {
             card.set_pos(pos);
             card.set_rotation(rotation);
+            card.set_scale(1.);
         }
     }
 
@@ -136,6 +193,11 @@ impl Hand {
                     canvas.draw(&outline, DrawParam::new());
                 }
             }
+            if self.hint == Some(card.id()) {
+                let outline =
+                    Mesh::new_polygon(ctx, DrawMode::stroke(3.), &card.corners(), HINT_COLOR)?;
+                canvas.draw(&outline, DrawParam::new());
+            }
         }
 
         Ok(())
@@ -1,12 +1,51 @@
-mod card;
+pub mod bot;
+pub mod card;
+#[cfg(feature = "gui")]
 mod cards;
+pub mod cli;
+#[cfg(feature = "gui")]
+mod clock;
+#[cfg(feature = "gui")]
 mod deck;
-mod error;
+#[cfg(feature = "gui")]
+mod discard;
+pub mod engine;
+pub mod error;
+pub mod external;
+#[cfg(feature = "gui")]
 pub mod game;
+#[cfg(feature = "gui")]
 mod game_scenes;
+#[cfg(feature = "gui")]
 mod hand;
+#[cfg(feature = "gui")]
+mod mat;
+pub mod network;
+#[cfg(feature = "gui")]
 mod player;
-mod scenes;
+pub mod presets;
+#[cfg(feature = "gui")]
+mod remote;
+pub mod replay;
+pub mod rules;
+#[cfg(feature = "gui")]
+pub mod scenes;
+#[cfg(feature = "gui")]
+mod score;
+#[cfg(feature = "gui")]
+mod seating;
+#[cfg(feature = "gui")]
+pub mod settings;
+#[cfg(feature = "gui")]
 mod sprite;
+#[cfg(feature = "gui")]
 pub mod storage;
-mod mat;
+#[cfg(feature = "gui")]
+mod theme;
+#[cfg(feature = "gui")]
+mod tutorial;
+
+/// Name of the game and of its author, which ggez builds the user
+/// directories from.
+pub const GAME_ID: &str = "durak_rust";
+pub const AUTHOR: &str = "alter_kaker";
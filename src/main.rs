@@ -1,9 +1,33 @@
 use ggez::{event, ContextBuilder, GameResult};
-use std::{env, path};
+use std::{env, path, process};
 
-use durak_rust::{game::Game, storage};
+use durak_rust::{
+    cli::{Options, USAGE},
+    error::DurakError,
+    game::Game,
+    settings::Settings,
+    AUTHOR, GAME_ID,
+};
+
+/// Reports arguments that make no sense and exits.
+fn usage_error(error: DurakError) -> ! {
+    eprintln!("{error}\n\n{USAGE}");
+    process::exit(2);
+}
 
 fn main() -> GameResult {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => usage_error(error),
+    };
+    if options.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    if let Err(error) = options.check_game() {
+        usage_error(error);
+    }
+
     let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
         let mut path = path::PathBuf::from(manifest_dir);
         path.push("resources");
@@ -12,12 +36,26 @@ fn main() -> GameResult {
         path::PathBuf::from("./resources")
     };
 
-    let cb = ContextBuilder::new("durak_rust", "alter_kaker").add_resource_path(resource_dir);
+    let cb = ContextBuilder::new(GAME_ID, AUTHOR).add_resource_path(resource_dir);
 
     let (mut ctx, event_loop) = cb.build()?;
-    storage::load_card_image(&ctx)?;
 
-    let state = Game::new(&mut ctx)?;
+    let settings = Settings::load(&ctx).unwrap_or_else(|error| {
+        error.report(&ctx);
+        Settings::default()
+    });
+    settings.apply_window(&mut ctx)?;
+
+    if let Some(address) = &options.host {
+        println!("Waiting at {address} for a player to join");
+    }
+    // Presets saved by the player are only known once the game has started,
+    // so some arguments can only be checked here.
+    let state = match Game::new(&mut ctx, settings, &options) {
+        Ok(state) => state,
+        Err(error @ DurakError::Usage(_)) => usage_error(error),
+        Err(error) => return Err(error.into()),
+    };
 
     event::run(ctx, event_loop, state)
 }
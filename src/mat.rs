@@ -5,24 +5,15 @@ use ggez::{
 };
 
 use crate::{
-    card::{Card, Rank, CARD_HEIGHT, CARD_WIDTH},
+    card::{Card, Suit, CARD_HEIGHT, CARD_WIDTH},
     cards::Cards,
     error::DurakError,
+    hand::HINT_COLOR,
+    rules,
 };
 
 pub struct Stack(Card, Option<Card>);
 
-impl Stack {
-    fn has_rank(&self, rank: Rank) -> bool {
-        self.0.rank() == rank
-            || if let Some(card) = &self.1 {
-                card.rank() == rank
-            } else {
-                false
-            }
-    }
-}
-
 impl From<Stack> for Vec<Card> {
     fn from(value: Stack) -> Self {
         [Some(value.0), value.1].into_iter().flatten().collect()
@@ -38,11 +29,22 @@ impl<'a> From<&'a Stack> for Vec<&'a Card> {
     }
 }
 
+/// Outline of the stacks a dragged card can cover and of a table that
+/// accepts it.
+const TARGET_COLOR: Color = Color::new(0.2, 1., 0.2, 0.7);
+/// Outline of a table that would reject the dragged card.
+const REJECT_COLOR: Color = Color::new(1., 0.2, 0.2, 0.7);
+
 #[derive(Default)]
 pub struct Mat {
     in_play: Vec<Stack>,
     rect: Rect,
     intersect: bool,
+    /// While a card is dragged: whether it can be played, and the stacks it
+    /// can cover.
+    drop_targets: Option<(bool, Vec<usize>)>,
+    /// Stack the advisor suggests covering.
+    hint: Option<usize>,
 }
 
 impl Mat {
@@ -53,7 +55,7 @@ impl Mat {
         }
     }
     pub fn attack(&mut self, card: Card) -> Option<Card> {
-        if self.in_play.is_empty() || self.in_play.iter().any(|stack| stack.has_rank(card.rank())) {
+        if rules::can_attack(self.cards().map(Card::id), card.id()) {
             self.in_play.push(Stack(card, None));
             self.set_card_params();
             None
@@ -62,9 +64,32 @@ impl Mat {
         }
     }
 
-    pub fn defend(&mut self, stack_idx: usize, card: Card) {
-        self.in_play[stack_idx].1 = Some(card);
-        self.set_card_params();
+    /// Covers the stack at `stack_idx` with `card`, or hands the card back if
+    /// it doesn't beat the attacking card or the stack is already covered.
+    pub fn defend(&mut self, stack_idx: usize, card: Card, trump: Suit) -> Option<Card> {
+        match self.in_play.get_mut(stack_idx) {
+            Some(stack) if stack.1.is_none() && rules::beats(stack.0.id(), card.id(), trump) => {
+                stack.1 = Some(card);
+                self.set_card_params();
+                None
+            }
+            _ => Some(card),
+        }
+    }
+
+    pub fn cards(&self) -> impl Iterator<Item = &Card> {
+        self.in_play
+            .iter()
+            .flat_map(<&Stack as Into<Vec<&Card>>>::into)
+    }
+
+    /// Index of the stack under `pos`.
+    pub fn stack_at(&self, pos: Vec2) -> Option<usize> {
+        self.in_play.iter().position(|stack| {
+            <&Stack as Into<Vec<&Card>>>::into(stack)
+                .iter()
+                .any(|card| card.intersect(pos))
+        })
     }
 
     pub fn take_cards(&mut self) -> Cards {
@@ -75,6 +100,14 @@ impl Mat {
         self.intersect = self.rect.contains(mouse_pos);
     }
 
+    pub fn set_drop_targets(&mut self, drop_targets: Option<(bool, Vec<usize>)>) {
+        self.drop_targets = drop_targets;
+    }
+
+    pub fn set_hint(&mut self, hint: Option<usize>) {
+        self.hint = hint;
+    }
+
     pub fn intersect(&self) -> bool {
         self.intersect
     }
@@ -85,9 +118,13 @@ impl Mat {
             let y = ((i / 2) + 1) as f32 * (CARD_HEIGHT + 20.) + self.rect.y;
             stack.0.set_pos(vec2(x, y));
             stack.0.set_rotation(0.);
+            stack.0.set_scale(1.);
+            stack.0.set_dimmed(false);
             if let Some(card) = stack.1.as_mut() {
                 card.set_pos(vec2(x, y + 15.));
-                card.set_rotation(0.)
+                card.set_rotation(0.);
+                card.set_scale(1.);
+                card.set_dimmed(false);
             }
         }
     }
@@ -101,18 +138,30 @@ impl Mat {
         {
             card.draw(canvas)?;
         }
+        if let Some(stack) = self.hint.and_then(|idx| self.in_play.get(idx)) {
+            let outline =
+                Mesh::new_polygon(ctx, DrawMode::stroke(3.), &stack.0.corners(), HINT_COLOR)?;
+            canvas.draw(&outline, DrawParam::new());
+        }
+        if let Some((_, stacks)) = &self.drop_targets {
+            for stack in stacks.iter().filter_map(|idx| self.in_play.get(*idx)) {
+                let outline =
+                    Mesh::new_polygon(ctx, DrawMode::stroke(3.), &stack.0.corners(), TARGET_COLOR)?;
+                canvas.draw(&outline, DrawParam::new());
+            }
+        }
         if self.intersect {
-            let outline = Mesh::new_rectangle(
-                ctx,
-                DrawMode::stroke(2.),
-                self.rect,
-                Color {
+            let color = match self.drop_targets {
+                Some((true, _)) => TARGET_COLOR,
+                Some((false, _)) => REJECT_COLOR,
+                None => Color {
                     r: 1.,
                     g: 1.,
                     b: 0.,
                     a: 0.5,
                 },
-            )?;
+            };
+            let outline = Mesh::new_rectangle(ctx, DrawMode::stroke(2.), self.rect, color)?;
             canvas.draw(&outline, DrawParam::new());
         }
 
@@ -0,0 +1,158 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::DurakError,
+    external::{ExternalBot, Finished, Move, Reply, Request},
+};
+
+/// Time a player who joined over the network gets for every move, before
+/// the built-in bot moves for them.
+pub const GUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// First line a player joining a game sends.
+#[derive(Debug, Serialize, Deserialize)]
+struct Hello {
+    name: String,
+}
+
+/// A line from the host of a game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Message {
+    /// It is the guest's move.
+    Request(Request),
+    Finished(Finished),
+}
+
+fn network(peer: &str) -> impl FnOnce(io::Error) -> DurakError + '_ {
+    move |source| DurakError::Network {
+        peer: peer.to_string(),
+        source,
+    }
+}
+
+/// Waits at `address` for a player to join and returns their name along
+/// with the bot playing their seat. The host runs the game and the guest is
+/// sent the same requests as a bot program, see [`ExternalBot`].
+pub fn host(address: &str) -> Result<(String, ExternalBot), DurakError> {
+    let listener = TcpListener::bind(address).map_err(network(address))?;
+    accept(&listener)
+}
+
+/// Waits for the next player to join at `listener`, like [`host`].
+pub fn accept(listener: &TcpListener) -> Result<(String, ExternalBot), DurakError> {
+    let address = listener
+        .local_addr()
+        .map(|address| address.to_string())
+        .unwrap_or_default();
+    let (stream, peer) = listener.accept().map_err(network(&address))?;
+    let peer = peer.to_string();
+    // Lines are short and answered one by one, so they shouldn't wait to be
+    // sent along with others.
+    stream.set_nodelay(true).map_err(network(&peer))?;
+    stream
+        .set_read_timeout(Some(GUEST_TIMEOUT))
+        .map_err(network(&peer))?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(network(&peer))?;
+    let hello: Hello = serde_json::from_str(&line)
+        .map_err(|error| DurakError::protocol(format!("{peer}: {error}")))?;
+    reader
+        .get_ref()
+        .set_read_timeout(None)
+        .map_err(network(&peer))?;
+    let bot = ExternalBot::guest(reader, GUEST_TIMEOUT).map_err(|error| match error {
+        DurakError::Io(source) => network(&peer)(source),
+        error => error,
+    })?;
+    Ok((hello.name, bot))
+}
+
+/// A game hosted by someone else, joined under a name.
+pub struct Connection {
+    peer: String,
+    stream: TcpStream,
+    /// Lines from the host, read on a thread of their own so they can be
+    /// looked for without waiting.
+    messages: Receiver<String>,
+}
+
+impl Connection {
+    pub fn join(address: &str, name: &str) -> Result<Connection, DurakError> {
+        let mut stream = TcpStream::connect(address).map_err(network(address))?;
+        stream.set_nodelay(true).map_err(network(address))?;
+        let hello = serde_json::to_string(&Hello {
+            name: name.to_string(),
+        })
+        .map_err(|error| DurakError::protocol(error.to_string()))?;
+        writeln!(stream, "{hello}").map_err(network(address))?;
+
+        let reader = BufReader::new(stream.try_clone().map_err(network(address))?);
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Connection {
+            peer: address.to_string(),
+            stream,
+            messages,
+        })
+    }
+
+    /// Waits for the next line from the host.
+    pub fn wait(&mut self) -> Result<Message, DurakError> {
+        match self.messages.recv() {
+            Ok(line) => self.parse(&line),
+            Err(_) => Err(self.closed()),
+        }
+    }
+
+    /// The next line from the host if one has come in, for games with a
+    /// frame loop.
+    pub fn poll(&mut self) -> Result<Option<Message>, DurakError> {
+        match self.messages.try_recv() {
+            Ok(line) => self.parse(&line).map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(self.closed()),
+        }
+    }
+
+    /// Answers the request `id` with `action`.
+    pub fn reply(&mut self, id: u64, action: Move) -> Result<(), DurakError> {
+        let reply = serde_json::to_string(&Reply { id, action })
+            .map_err(|error| DurakError::protocol(error.to_string()))?;
+        writeln!(self.stream, "{reply}").map_err(network(&self.peer))
+    }
+
+    fn parse(&self, line: &str) -> Result<Message, DurakError> {
+        serde_json::from_str(line)
+            .map_err(|error| DurakError::protocol(format!("{}: {error}", self.peer)))
+    }
+
+    fn closed(&self) -> DurakError {
+        network(&self.peer)(io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            "the host closed the game",
+        ))
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Also ends the thread reading from the host.
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
@@ -5,6 +5,10 @@ pub struct Player {
     pub name: String,
     pub hand: Hand,
     pub human: bool,
+    /// Team in team play, `0` or `1`.
+    pub team: usize,
+    /// Command line of a program playing this bot instead of the built-in one.
+    pub program: Option<String>,
 }
 
 impl Player {
@@ -13,6 +17,8 @@ impl Player {
             name,
             hand: Hand::new(),
             human,
+            team: 0,
+            program: None,
         }
     }
 
@@ -25,3 +31,12 @@ impl Player {
         self.hand.push(card);
     }
 }
+
+/// A name two of `players` go by, if any. Scores are kept by name, so every
+/// player needs one of their own.
+pub fn shared_name(players: &[Player]) -> Option<&str> {
+    players.iter().enumerate().find_map(|(i, player)| {
+        let name = player.name.as_str();
+        (!name.is_empty() && players[..i].iter().any(|other| other.name == name)).then_some(name)
+    })
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "gui")]
+use std::io::{Read, Write};
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use directories::ProjectDirs;
+#[cfg(feature = "gui")]
+use ggez::Context;
+
+use crate::{error::DurakError, rules::Rules, AUTHOR, GAME_ID};
+
+/// Rule presets saved by the player, in the user config directory.
+const PRESETS_PATH: &str = "/presets.toml";
+
+/// Rule presets by name: the built-in ones and those saved by the player.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Presets {
+    saved: BTreeMap<String, Rules>,
+}
+
+impl Presets {
+    /// Reads the presets file, if there is one, on top of the built-in
+    /// presets.
+    #[cfg(feature = "gui")]
+    pub fn load(ctx: &Context) -> Result<Presets, DurakError> {
+        if !ctx.fs.exists(PRESETS_PATH) {
+            return Ok(Presets::default());
+        }
+        let mut text = String::new();
+        ctx.fs.open(PRESETS_PATH)?.read_to_string(&mut text)?;
+        Presets::parse(&text)
+    }
+
+    /// Reads the presets file saved by the game without going through ggez,
+    /// for tools that don't open a window.
+    pub fn load_user() -> Result<Presets, DurakError> {
+        let Some(dirs) = ProjectDirs::from("", AUTHOR, GAME_ID) else {
+            return Ok(Presets::default());
+        };
+        let path: PathBuf = dirs.config_dir().join(PRESETS_PATH.trim_start_matches('/'));
+        match fs::read_to_string(path) {
+            Ok(text) => Presets::parse(&text),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Presets::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Presets, DurakError> {
+        let saved = toml::from_str(text)
+            .map_err(|error| DurakError::state(format!("{PRESETS_PATH}: {error}")))?;
+        Ok(Presets { saved })
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn save(&self, ctx: &Context) -> Result<(), DurakError> {
+        let text = toml::to_string(&self.saved)
+            .map_err(|error| DurakError::state(format!("{PRESETS_PATH}: {error}")))?;
+        ctx.fs.create(PRESETS_PATH)?.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Names of every preset, built-in ones first.
+    pub fn names(&self) -> Vec<&str> {
+        Rules::BUILT_IN
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(self.saved.keys().map(String::as_str))
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rules> {
+        Rules::BUILT_IN
+            .iter()
+            .find(|(other, _)| *other == name)
+            .map(|(_, rules)| *rules)
+            .or_else(|| self.saved.get(name).copied())
+    }
+
+    /// Adds or replaces a preset of the player's. Built-in presets can't be
+    /// replaced.
+    pub fn insert(&mut self, name: &str, rules: Rules) -> Result<(), DurakError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DurakError::rules("the preset needs a name"));
+        }
+        if Rules::BUILT_IN.iter().any(|(other, _)| *other == name) {
+            return Err(DurakError::rules(format!("{name} is a built-in preset")));
+        }
+        self.saved.insert(name.to_string(), rules);
+        Ok(())
+    }
+}
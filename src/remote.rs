@@ -0,0 +1,152 @@
+use ggegui::{
+    egui::{Area, Color32},
+    Gui,
+};
+use ggez::{
+    graphics::{Canvas, Color, DrawParam},
+    Context,
+};
+
+use crate::{
+    error::DurakError,
+    external::{Move, Request},
+    game::DurakState,
+    game_scenes::{DurakSceneTransition, MainMenu},
+    network::{Connection, Message},
+    scenes::{NewScene, Scene, SceneResult, SceneSwitch},
+};
+
+/// A game hosted by someone else. The host sends the table as seen from
+/// this seat whenever it is this player's move, and the moves they may
+/// make, which are offered as buttons.
+pub struct RemoteGame {
+    state: DurakState,
+    connection: Connection,
+    /// What the host sent last, still shown while the others move.
+    request: Option<Request>,
+    /// The last request has been answered.
+    answered: bool,
+    /// Result of the last game, until the next one starts.
+    result: Option<String>,
+    /// Why the game can't go on.
+    error: Option<String>,
+}
+
+impl RemoteGame {
+    /// Takes in what the host sent since the last frame.
+    fn receive(&mut self) {
+        while self.error.is_none() {
+            match self.connection.poll() {
+                Ok(Some(Message::Request(request))) => {
+                    self.request = Some(request);
+                    self.answered = false;
+                    self.result = None;
+                }
+                Ok(Some(Message::Finished(finished))) => self.result = Some(finished.result),
+                Ok(None) => break,
+                // The host leaves once the game is over.
+                Err(_) if self.result.is_some() => {
+                    self.error = Some(String::from("The host has left"))
+                }
+                Err(error) => self.error = Some(error.to_string()),
+            }
+        }
+    }
+
+    fn answer(&mut self, action: Move) {
+        let Some(request) = &self.request else {
+            return;
+        };
+        match self.connection.reply(request.id, action) {
+            Ok(()) => self.answered = true,
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+}
+
+impl Scene for RemoteGame {
+    type State = DurakState;
+    type Error = DurakError;
+
+    fn update(mut self: Box<Self>, gui: &mut Gui, ctx: &mut Context) -> SceneResult<Self> {
+        self.receive();
+        let mut clicked = None;
+        let menu = Area::new("id")
+            .show(&gui.ctx(), |ui| {
+                ui.heading("Network game");
+                match &self.request {
+                    Some(request) => {
+                        for line in request.summary() {
+                            ui.label(line);
+                        }
+                        if self.answered || self.result.is_some() {
+                            ui.label("Waiting for the others");
+                        } else {
+                            ui.label("Your move");
+                            ui.horizontal_wrapped(|ui| {
+                                for action in &request.legal {
+                                    if ui.button(request.label(action)).clicked() {
+                                        clicked = Some(action.clone());
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    None => {
+                        ui.label("Waiting for the host to start");
+                    }
+                }
+                if let Some(result) = &self.result {
+                    ui.colored_label(Color32::LIGHT_GREEN, result);
+                }
+                if let Some(error) = &self.error {
+                    ui.colored_label(Color32::LIGHT_RED, error);
+                }
+                ui.button("Main Menu").clicked()
+            })
+            .inner;
+        gui.update(ctx);
+
+        if menu {
+            self.state.join = None;
+            return <Self as DurakSceneTransition<MainMenu>>::switch(self, ctx);
+        }
+        if let Some(action) = clicked {
+            self.answer(action);
+        }
+        Ok(SceneSwitch::Stay(self))
+    }
+
+    fn draw(&self, gui: &Gui, ctx: &mut Context) -> Result<(), DurakError> {
+        let mut canvas = Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+
+        gui.draw(&mut canvas, DrawParam::new());
+        canvas.finish(ctx)?;
+
+        Ok(())
+    }
+
+    /// Joins the game at the address in `state` under the name of the local
+    /// player.
+    fn new(state: DurakState, _ctx: &Context) -> NewScene<RemoteGame> {
+        let Some(address) = state.join.clone() else {
+            return Err((DurakError::state("no game to join"), state));
+        };
+        let connection = match Connection::join(&address, &state.players[0].name) {
+            Ok(connection) => connection,
+            Err(error) => return Err((error, state)),
+        };
+        Ok(RemoteGame {
+            state,
+            connection,
+            request: None,
+            answered: false,
+            result: None,
+            error: None,
+        })
+    }
+
+    fn take_state(self: Box<Self>) -> DurakState {
+        self.state
+    }
+}
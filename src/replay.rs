@@ -0,0 +1,126 @@
+#[cfg(feature = "gui")]
+use std::io::Write;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+#[cfg(feature = "gui")]
+use ggez::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{Action, Engine, Event},
+    error::DurakError,
+    rules::{CardId, DeckSize, Rules},
+    AUTHOR, GAME_ID,
+};
+
+/// The last game played, in the user config directory.
+const LAST_GAME_PATH: &str = "/last-game.json";
+
+/// A game written down so it can be played back: who sat where, the deal and
+/// every move made.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    /// Names of the players, by seat.
+    pub players: Vec<String>,
+    /// Each player's team in team play.
+    pub teams: Option<Vec<usize>>,
+    pub rules: Rules,
+    pub deck_size: DeckSize,
+    pub decks: usize,
+    /// The deck before dealing, as in [`Engine::deal`].
+    pub deal: Vec<CardId>,
+    /// Player picked to lead the first bout, if not the lowest trump.
+    pub leader: Option<usize>,
+    pub log: Vec<(usize, Action)>,
+}
+
+impl Replay {
+    /// Writes down the game `engine` has played so far between `players`,
+    /// dealt from `decks` decks of `deck_size`.
+    pub fn record(
+        players: Vec<String>,
+        engine: &Engine,
+        deck_size: DeckSize,
+        decks: usize,
+    ) -> Self {
+        let teams = (0..engine.players())
+            .map(|player| engine.team(player))
+            .collect();
+        Replay {
+            players,
+            teams,
+            rules: *engine.rules(),
+            deck_size,
+            decks,
+            deal: engine.deal().to_vec(),
+            leader: engine.leader(),
+            log: engine.log().to_vec(),
+        }
+    }
+
+    /// The game as dealt, before the first move, along with the cards drawn.
+    pub fn start(&self) -> Result<(Engine, Vec<Event>), DurakError> {
+        let (mut engine, events) = Engine::new(
+            self.deal.clone(),
+            self.players.len(),
+            self.teams.clone(),
+            self.rules,
+        )?;
+        if let Some(leader) = self.leader {
+            engine.set_leader(leader);
+        }
+        Ok((engine, events))
+    }
+
+    /// The game played to its last move. [`Engine::rewind`] takes it back to
+    /// any move before.
+    pub fn game(&self) -> Result<Engine, DurakError> {
+        let (mut engine, _) = self.start()?;
+        for (i, (player, action)) in self.log.iter().enumerate() {
+            if *player >= self.players.len() {
+                return Err(DurakError::state(format!("move {} has no player", i + 1)));
+            }
+            engine.apply(*player, *action).map_err(|error| {
+                DurakError::state(format!("move {} can't be played back: {error}", i + 1))
+            })?;
+        }
+        Ok(engine)
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, DurakError> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text)
+            .map_err(|error| DurakError::state(format!("{}: {error}", path.display())))
+    }
+
+    /// Keeps the game as the last one played, next to the settings.
+    #[cfg(feature = "gui")]
+    pub fn save_last(&self, ctx: &Context) -> Result<(), DurakError> {
+        ctx.fs
+            .create(LAST_GAME_PATH)?
+            .write_all(self.to_json()?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Keeps the game as the last one played without going through ggez,
+    /// for tools that don't open a window.
+    pub fn save_last_user(&self) -> Result<(), DurakError> {
+        let Some(dirs) = ProjectDirs::from("", AUTHOR, GAME_ID) else {
+            return Ok(());
+        };
+        fs::create_dir_all(dirs.config_dir())?;
+        let path: PathBuf = dirs
+            .config_dir()
+            .join(LAST_GAME_PATH.trim_start_matches('/'));
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    fn to_json(&self) -> Result<String, DurakError> {
+        serde_json::to_string(self).map_err(|error| DurakError::state(error.to_string()))
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt::Display;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Rank, Suit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeckSize {
+    /// 24 cards, Nine to Ace.
+    Short,
+    /// 36 cards, Six to Ace.
+    Standard,
+    /// 52 cards, Two to Ace.
+    Full,
+}
+
+impl DeckSize {
+    pub const ALL: [DeckSize; 3] = [DeckSize::Short, DeckSize::Standard, DeckSize::Full];
+
+    pub fn lowest_rank(self) -> Rank {
+        match self {
+            DeckSize::Short => Rank::Nine,
+            DeckSize::Standard => Rank::Six,
+            DeckSize::Full => Rank::Two,
+        }
+    }
+
+    pub fn ranks(self) -> impl Iterator<Item = Rank> {
+        Rank::ALL
+            .into_iter()
+            .filter(move |rank| *rank >= self.lowest_rank())
+    }
+
+    pub fn cards(self) -> usize {
+        self.ranks().count() * Suit::ALL.len()
+    }
+}
+
+/// Most players that can be dealt a hand of `deal_size` from `decks` decks of
+/// `size`.
+pub fn max_players(size: DeckSize, decks: usize, deal_size: usize) -> usize {
+    size.cards() * decks / deal_size.max(1)
+}
+
+/// House rules the engine plays by. Picked from a preset in the main menu
+/// and tweaked there; missing entries in a presets file fall back to the
+/// classic rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// Cards dealt to each player, and the number hands are topped up to.
+    pub deal_size: usize,
+    /// Most attacking cards in a single bout.
+    pub bout_limit: usize,
+    /// Most attacking cards in the first bout of the game.
+    pub first_bout_limit: usize,
+    /// The defender may pass the attack on with a card of the same rank.
+    pub transfers: bool,
+    /// The defender may pass the attack on by showing a trump of the same
+    /// rank and keeping it, once per card and bout.
+    pub show_trump: bool,
+    /// Anyone playing against the defender may throw in, not just the player
+    /// who led the bout.
+    pub neighbours_throw_in: bool,
+    /// Everyone getting rid of their cards in the same bout is a draw.
+    /// Otherwise whoever led that bout loses.
+    pub draws: bool,
+}
+
+impl Rules {
+    /// Throw-in Durak, the first bout limited to five cards.
+    pub const CLASSIC: Rules = Rules {
+        deal_size: 6,
+        bout_limit: 6,
+        first_bout_limit: 5,
+        transfers: false,
+        show_trump: false,
+        neighbours_throw_in: true,
+        draws: true,
+    };
+
+    /// Presets that are always there, by name.
+    pub const BUILT_IN: [(&'static str, Rules); 3] = [
+        ("Classic", Rules::CLASSIC),
+        (
+            "Transfer",
+            Rules {
+                transfers: true,
+                show_trump: true,
+                ..Rules::CLASSIC
+            },
+        ),
+        (
+            "Simple",
+            Rules {
+                first_bout_limit: 6,
+                neighbours_throw_in: false,
+                ..Rules::CLASSIC
+            },
+        ),
+    ];
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::CLASSIC
+    }
+}
+
+/// Identity of a card without its sprites. With more than one deck in play
+/// two cards can share suit and rank, so `deck_id` tells them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CardId {
+    pub suit: Suit,
+    pub rank: Rank,
+    pub deck_id: usize,
+}
+
+impl Display for CardId {
+    /// Rank and suit symbol, e.g. `10♥`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.rank.label(), self.suit.symbol())
+    }
+}
+
+/// Rank and suit of a card written like [`CardId`]'s `Display`, e.g. `10♥`.
+/// The suit may also be given by its initial, as in `10h`.
+pub fn parse_card(text: &str) -> Option<(Rank, Suit)> {
+    let suit = text.chars().last()?;
+    let rank = &text[..text.len() - suit.len_utf8()];
+    Some((Rank::from_label(rank)?, Suit::from_char(suit)?))
+}
+
+/// Every card of `decks` decks of `size`, unshuffled.
+pub fn card_ids(size: DeckSize, decks: usize) -> Vec<CardId> {
+    (0..decks)
+        .flat_map(|deck_id| {
+            Suit::ALL.into_iter().flat_map(move |suit| {
+                size.ranks().map(move |rank| CardId {
+                    suit,
+                    rank,
+                    deck_id,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Shuffles `cards` in place. Every deal goes through here, so a seed deals
+/// the same game in the window, in the terminal and in the tournament.
+pub fn shuffle<T>(cards: &mut [T], rng: &mut impl Rng) {
+    let len = cards.len();
+    for i in 0..len {
+        let r = i + rng.gen_range(0..(len - i));
+        cards.swap(i, r);
+    }
+}
+
+/// Whether `defence` covers `attack`. A card never beats its own duplicate
+/// from another deck, since the rank has to be strictly higher.
+pub fn beats(attack: CardId, defence: CardId, trump: Suit) -> bool {
+    if defence.suit == attack.suit {
+        defence.rank > attack.rank
+    } else {
+        defence.suit == trump
+    }
+}
+
+/// Whether `card` may be added to a table holding `table`: the first attack
+/// can be anything, later ones have to match a rank already on the table.
+pub fn can_attack(table: impl IntoIterator<Item = CardId>, card: CardId) -> bool {
+    let mut table = table.into_iter().peekable();
+    table.peek().is_none() || table.any(|other| other.rank == card.rank)
+}
@@ -1,18 +1,157 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use ggegui::Gui;
-use ggez::Context;
+use ggez::{
+    glam::{vec2, Vec2},
+    graphics::{Canvas, Color, DrawParam, Image},
+    Context, GameError,
+};
 
-pub type SceneResult<S> = Result<
-    Box<dyn Scene<State = <S as Scene>::State, Error = <S as Scene>::Error>>,
-    <S as Scene>::Error,
->;
+pub type SceneResult<S> =
+    Result<SceneSwitch<<S as Scene>::State, <S as Scene>::Error>, <S as Scene>::Error>;
+
+/// Result of setting up a scene. A scene that can't be set up gives back
+/// the state it was handed along with the error.
+pub type NewScene<S> = Result<S, (<S as Scene>::Error, <S as Scene>::State)>;
+
+pub enum SceneSwitch<S, E> {
+    Stay(Box<dyn Scene<State = S, Error = E>>),
+    Switch(Box<dyn Scene<State = S, Error = E>>, Transition),
+    /// The scene failed and gave up its state, which the error handler gets
+    /// to carry on with.
+    Failed(S, E),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transition {
+    Cut,
+    Fade(Duration),
+    Slide(Duration, SlideDirection),
+}
+
+impl Transition {
+    /// The same effect played `speed` times as fast.
+    pub fn faster(self, speed: f32) -> Transition {
+        match self {
+            Transition::Cut => Transition::Cut,
+            Transition::Fade(duration) => Transition::Fade(duration.div_f32(speed)),
+            Transition::Slide(duration, direction) => {
+                Transition::Slide(duration.div_f32(speed), direction)
+            }
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        match self {
+            Transition::Cut => Duration::ZERO,
+            Transition::Fade(duration) | Transition::Slide(duration, _) => *duration,
+        }
+    }
+}
+
+/// Direction in which the outgoing scene leaves the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlideDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl SlideDirection {
+    fn vec(&self) -> Vec2 {
+        match self {
+            SlideDirection::Left => vec2(-1., 0.),
+            SlideDirection::Right => vec2(1., 0.),
+            SlideDirection::Up => vec2(0., -1.),
+            SlideDirection::Down => vec2(0., 1.),
+        }
+    }
+}
+
+/// A transition in progress. The outgoing scene's state has already moved
+/// into the incoming scene, so its last frame is kept as an image instead.
+struct ActiveTransition {
+    effect: Transition,
+    elapsed: Duration,
+    outgoing: Image,
+    incoming: Image,
+    captured: bool,
+}
+
+impl ActiveTransition {
+    fn new(effect: Transition, ctx: &Context) -> Self {
+        let frame = ctx.gfx.frame();
+        let (format, width, height) = (frame.format(), frame.width(), frame.height());
+        ActiveTransition {
+            effect,
+            elapsed: Duration::ZERO,
+            outgoing: Image::new_canvas_image(ctx, format, width, height, 1),
+            incoming: Image::new_canvas_image(ctx, format, width, height, 1),
+            captured: false,
+        }
+    }
+
+    fn progress(&self) -> f32 {
+        (self.elapsed.as_secs_f32() / self.effect.duration().as_secs_f32()).min(1.)
+    }
+
+    fn finished(&self) -> bool {
+        self.elapsed >= self.effect.duration()
+    }
+
+    fn copy_frame(ctx: &mut Context, target: &Image) -> Result<(), GameError> {
+        let frame = ctx.gfx.frame().clone();
+        let mut canvas = Canvas::from_image(ctx, target.clone(), Color::BLACK);
+        canvas.draw(&frame, DrawParam::new());
+        canvas.finish(ctx)
+    }
+
+    /// Called before the incoming scene draws, while the frame still holds
+    /// the last picture of the outgoing scene.
+    fn capture(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        if !self.captured {
+            Self::copy_frame(ctx, &self.outgoing)?;
+            self.captured = true;
+        }
+        Ok(())
+    }
+
+    /// Called after the incoming scene has drawn itself to the frame.
+    fn composite(&self, ctx: &mut Context) -> Result<(), GameError> {
+        let t = self.progress();
+        match self.effect {
+            Transition::Cut => Ok(()),
+            Transition::Fade(_) => {
+                let mut canvas = Canvas::from_frame(ctx, None);
+                canvas.draw(
+                    &self.outgoing,
+                    DrawParam::new().color(Color::new(1., 1., 1., 1. - t)),
+                );
+                canvas.finish(ctx)
+            }
+            Transition::Slide(_, direction) => {
+                Self::copy_frame(ctx, &self.incoming)?;
+                let extent = vec2(self.incoming.width() as f32, self.incoming.height() as f32);
+                let offset = direction.vec() * extent;
+
+                let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+                canvas.draw(&self.outgoing, DrawParam::new().dest(offset * t));
+                canvas.draw(&self.incoming, DrawParam::new().dest(offset * (t - 1.)));
+                canvas.finish(ctx)
+            }
+        }
+    }
+}
 
 pub struct SceneWrapper<S, E>
 where
     E: From<SceneError> + Debug,
 {
     scene: Option<Box<dyn Scene<State = S, Error = E>>>,
+    transition: Option<ActiveTransition>,
+    /// State given up by a failed scene, until the error handler takes it.
+    failed: Option<S>,
 }
 
 impl<S, E> SceneWrapper<S, E>
@@ -20,11 +159,45 @@ where
     E: From<SceneError> + Debug,
 {
     pub fn new(scene: Box<dyn Scene<State = S, Error = E>>) -> Self {
-        SceneWrapper { scene: Some(scene) }
+        SceneWrapper {
+            scene: Some(scene),
+            transition: None,
+            failed: None,
+        }
+    }
+
+    /// Takes the state out of the scene that failed, or out of the current
+    /// scene, leaving no scene behind.
+    pub fn take_state(&mut self) -> Option<S> {
+        self.transition = None;
+        let scene = self.scene.take();
+        self.failed
+            .take()
+            .or_else(|| scene.map(|scene| scene.take_state()))
     }
     pub fn update(&mut self, gui: &mut Gui, ctx: &mut ggez::Context) -> Result<(), E> {
+        if let Some(transition) = self.transition.as_mut() {
+            transition.elapsed += ctx.time.delta();
+            if transition.finished() {
+                self.transition = None;
+            }
+        }
+
         if let Some(scene) = self.scene.take() {
-            self.scene = Some(scene.update(gui, ctx)?);
+            match scene.update(gui, ctx)? {
+                SceneSwitch::Stay(scene) => self.scene = Some(scene),
+                SceneSwitch::Switch(scene, effect) => {
+                    self.scene = Some(scene);
+                    self.transition = match effect {
+                        Transition::Cut => None,
+                        effect => Some(ActiveTransition::new(effect, ctx)),
+                    };
+                }
+                SceneSwitch::Failed(state, error) => {
+                    self.failed = Some(state);
+                    return Err(error);
+                }
+            }
             Ok(())
         } else {
             Err(SceneError::SceneMissing.into())
@@ -33,7 +206,14 @@ where
 
     pub fn draw(&mut self, gui: &Gui, ctx: &mut ggez::Context) -> Result<(), E> {
         if let Some(scene) = self.scene.as_ref() {
-            scene.draw(gui, ctx)
+            if let Some(transition) = self.transition.as_mut() {
+                transition.capture(ctx).map_err(SceneError::Render)?;
+                scene.draw(gui, ctx)?;
+                transition.composite(ctx).map_err(SceneError::Render)?;
+                Ok(())
+            } else {
+                scene.draw(gui, ctx)
+            }
         } else {
             Err(SceneError::SceneMissing.into())
         }
@@ -99,18 +279,17 @@ pub trait Scene {
     ) -> Result<(), Self::Error> {
         Ok(())
     }
-    fn new(state: Self::State, ctx: &Context) -> Result<Self, Self::Error>
+    fn new(state: Self::State, ctx: &Context) -> NewScene<Self>
     where
         Self: Sized;
 
-    fn take_state(self) -> Self::State
-    where
-        Self: Sized;
+    fn take_state(self: Box<Self>) -> Self::State;
 }
 
 #[derive(Debug)]
 pub enum SceneError {
     SceneMissing,
+    Render(GameError),
 }
 
 pub trait SceneTransition<U, S>
@@ -118,7 +297,21 @@ where
     Self: Scene<State = S> + Sized,
     U: Scene<State = S>,
 {
-    fn transition(self: Box<Self>, ctx: &Context) -> Result<U, U::Error> {
-        U::new(self.take_state(), ctx)
+    /// The effect to switch with, which may depend on `state`, e.g. on how
+    /// fast the player likes animations.
+    fn effect(_state: &S) -> Transition {
+        Transition::Cut
+    }
+
+    fn switch(self: Box<Self>, ctx: &Context) -> SceneResult<Self>
+    where
+        U: Scene<Error = Self::Error> + 'static,
+    {
+        let state = self.take_state();
+        let effect = Self::effect(&state);
+        Ok(match U::new(state, ctx) {
+            Ok(scene) => SceneSwitch::Switch(Box::new(scene), effect),
+            Err((error, state)) => SceneSwitch::Failed(state, error),
+        })
     }
 }
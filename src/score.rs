@@ -0,0 +1,156 @@
+use std::cmp::Reverse;
+
+/// When a match is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchGoal {
+    /// Every game stands on its own, scores just add up.
+    Single,
+    /// After this many games.
+    Games(u32),
+    /// As soon as someone reaches this many points with nobody level with
+    /// them.
+    Points(u32),
+}
+
+/// Points for each place in the order players get rid of their cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scoring {
+    /// The first one out scores a point for every other player, the next one
+    /// a point less and so on.
+    Ladder,
+    /// Points for the first, second and later places out; places past the
+    /// end score nothing.
+    Places(Vec<u32>),
+}
+
+impl Scoring {
+    /// Points for getting rid of one's cards in `place`, counted from 0, in
+    /// a game of `players`.
+    pub fn points(&self, place: usize, players: usize) -> u32 {
+        match self {
+            Scoring::Ladder => players.saturating_sub(place + 1) as u32,
+            Scoring::Places(points) => points.get(place).copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Running scores over the games of a match. Players are kept by name since
+/// seats can change between games, so no two players may share a name; in
+/// team play the teams are scored instead.
+#[derive(Debug, Clone)]
+pub struct Tally {
+    pub goal: MatchGoal,
+    pub scoring: Scoring,
+    scores: Vec<(String, u32)>,
+    games: u32,
+    last_loser: Option<String>,
+}
+
+impl Default for Tally {
+    fn default() -> Self {
+        Tally {
+            goal: MatchGoal::Single,
+            scoring: Scoring::Ladder,
+            scores: Vec::new(),
+            games: 0,
+            last_loser: None,
+        }
+    }
+}
+
+impl Tally {
+    /// Scores a finished game. `finished` holds the players who got rid of
+    /// their cards in the order they did, who score as [`Self::scoring`]
+    /// says. Whoever is left holding cards scores nothing.
+    pub fn record(&mut self, names: &[&str], finished: &[usize]) {
+        for (place, player) in finished.iter().enumerate() {
+            *self.score_mut(names[*player]) += self.scoring.points(place, names.len());
+        }
+        let losers: Vec<&str> = (0..names.len())
+            .filter(|player| !finished.contains(player))
+            .map(|player| names[player])
+            .collect();
+        for loser in &losers {
+            self.score_mut(loser);
+        }
+        self.last_loser = losers.first().map(|name| name.to_string());
+        self.games += 1;
+    }
+
+    /// Scores a finished team game: the winning team scores a point, a draw
+    /// scores nothing. `loser` is whoever was left holding cards.
+    pub fn record_teams(
+        &mut self,
+        teams: &[&str],
+        losing_team: Option<usize>,
+        loser: Option<&str>,
+    ) {
+        for (team, name) in teams.iter().enumerate() {
+            let points = self.score_mut(name);
+            if losing_team.is_some_and(|losing_team| losing_team != team) {
+                *points += 1;
+            }
+        }
+        self.last_loser = loser.map(str::to_string);
+        self.games += 1;
+    }
+
+    fn score_mut(&mut self, name: &str) -> &mut u32 {
+        let idx = match self.scores.iter().position(|(other, _)| other == name) {
+            Some(idx) => idx,
+            None => {
+                self.scores.push((name.to_string(), 0));
+                self.scores.len() - 1
+            }
+        };
+        &mut self.scores[idx].1
+    }
+
+    /// Names and points, best first.
+    pub fn scores(&self) -> Vec<(&str, u32)> {
+        let mut scores: Vec<(&str, u32)> = self
+            .scores
+            .iter()
+            .map(|(name, points)| (name.as_str(), *points))
+            .collect();
+        scores.sort_by_key(|(_, points)| Reverse(*points));
+        scores
+    }
+
+    pub fn games(&self) -> u32 {
+        self.games
+    }
+
+    /// Who was left holding cards in the last game, if anyone.
+    pub fn last_loser(&self) -> Option<&str> {
+        self.last_loser.as_deref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        match self.goal {
+            MatchGoal::Single => false,
+            MatchGoal::Games(games) => self.games >= games,
+            MatchGoal::Points(points) => {
+                let winners = self.winners();
+                winners.len() == 1 && winners[0].1 >= points
+            }
+        }
+    }
+
+    /// Everyone with the most points, more than one if they are level.
+    pub fn winners(&self) -> Vec<(&str, u32)> {
+        let scores = self.scores();
+        let best = scores.first().map(|(_, points)| *points);
+        scores
+            .into_iter()
+            .take_while(|(_, points)| Some(*points) == best)
+            .collect()
+    }
+
+    /// Starts a new match with the same goal and scoring.
+    pub fn reset(&mut self) {
+        self.scores.clear();
+        self.games = 0;
+        self.last_loser = None;
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use ggez::{
+    glam::{vec2, Vec2},
+    graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, Text, TextLayout},
+    Context,
+};
+
+use crate::error::DurakError;
+
+/// Most players the table has room for.
+pub const MAX_SEATS: usize = 6;
+
+/// Opponents are spread over this arc at the top of the table, measured
+/// clockwise from the local player's seat at the bottom.
+const OPPONENT_ARC: (f32, f32) = (60., 300.);
+
+/// Seconds left at which a running clock turns red.
+const LOW_TIME: u64 = 5;
+
+/// Highlight for the player who has to move.
+const ACTIVE_COLOR: Color = Color::new(1., 0.85, 0.2, 1.);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Seat {
+    pub pos: Vec2,
+    pub rotation: f32,
+    /// Where the name and hand size are shown, just behind the hand.
+    pub label_pos: Vec2,
+}
+
+impl Seat {
+    /// Seats for `players` players around a table at `center`, in turn
+    /// order. The player at index `local` sits at the bottom, the others
+    /// follow clockwise.
+    pub fn layout(players: usize, local: usize, center: Vec2, radius: f32) -> Vec<Seat> {
+        let opponents = players.saturating_sub(1) as f32;
+        let (from, to) = OPPONENT_ARC;
+
+        (0..players)
+            .map(|i| {
+                let seat = (i + players - local) % players;
+                let angle = if seat == 0 {
+                    0.
+                } else {
+                    from + (to - from) * seat as f32 / (opponents + 1.)
+                };
+                let rotation = angle.to_radians();
+                let direction = Vec2::from_angle(rotation).rotate(vec2(0., 1.));
+
+                Seat {
+                    pos: center + direction * radius,
+                    rotation,
+                    label_pos: center + direction * (radius + 20.),
+                }
+            })
+            .collect()
+    }
+
+    /// Draws the player's name with a badge showing how many cards they hold.
+    /// The name of a player whose move it is is highlighted and marked.
+    pub fn draw_label(
+        &self,
+        canvas: &mut Canvas,
+        ctx: &mut Context,
+        name: &str,
+        cards: usize,
+        active: bool,
+    ) -> Result<(), DurakError> {
+        let mut label = Text::new(name);
+        label
+            .set_font("IBM_CGA")
+            .set_scale(16.)
+            .set_layout(TextLayout::center());
+        let width = label.measure(ctx)?.x;
+        let color = if active { ACTIVE_COLOR } else { Color::WHITE };
+        canvas.draw(&label, DrawParam::new().dest(self.label_pos).color(color));
+
+        if active {
+            let marker_pos = self.label_pos - vec2(width / 2. + 14., 0.);
+            let marker = Mesh::new_polygon(
+                ctx,
+                DrawMode::fill(),
+                &[vec2(-6., -6.), vec2(6., 0.), vec2(-6., 6.)],
+                ACTIVE_COLOR,
+            )?;
+            canvas.draw(&marker, DrawParam::new().dest(marker_pos));
+        }
+
+        let badge_pos = self.label_pos + vec2(width / 2. + 14., 0.);
+        let badge = Mesh::new_circle(ctx, DrawMode::fill(), Vec2::ZERO, 10., 0.5, Color::WHITE)?;
+        canvas.draw(&badge, DrawParam::new().dest(badge_pos));
+
+        let mut count = Text::new(cards.to_string());
+        count
+            .set_font("IBM_CGA")
+            .set_scale(12.)
+            .set_layout(TextLayout::center());
+        canvas.draw(&count, DrawParam::new().dest(badge_pos).color(Color::BLACK));
+
+        Ok(())
+    }
+
+    /// Draws the time a player has left below their name, in red when it's
+    /// running out.
+    pub fn draw_clock(&self, canvas: &mut Canvas, remaining: Duration, active: bool) {
+        let seconds = remaining.as_secs_f32().ceil() as u64;
+        let mut clock = Text::new(format!("{}:{:02}", seconds / 60, seconds % 60));
+        clock
+            .set_font("IBM_CGA")
+            .set_scale(14.)
+            .set_layout(TextLayout::center());
+        let color = if active && seconds <= LOW_TIME {
+            Color::RED
+        } else if active {
+            ACTIVE_COLOR
+        } else {
+            Color::WHITE
+        };
+        canvas.draw(
+            &clock,
+            DrawParam::new()
+                .dest(self.label_pos + vec2(0., 18.))
+                .color(color),
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    io::{Read, Write},
+    ops::RangeInclusive,
+};
+
+use ggez::{
+    conf::{FullscreenType, WindowMode},
+    Context,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{bot::Difficulty, error::DurakError, rules::Rules, theme::DEFAULT_THEME};
+
+/// Settings file in the user config directory.
+const SETTINGS_PATH: &str = "/settings.toml";
+
+/// Values offered on the settings screen. Values read from the file are
+/// kept within them too.
+pub const WINDOW_WIDTHS: RangeInclusive<f32> = 640.0..=2560.0;
+pub const WINDOW_HEIGHTS: RangeInclusive<f32> = 480.0..=1440.0;
+pub const ANIMATION_SPEEDS: RangeInclusive<f32> = 0.25..=4.0;
+
+/// Preferences kept between runs. Missing entries in the file fall back to
+/// their defaults, so older files keep working.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window_width: f32,
+    pub window_height: f32,
+    pub fullscreen: bool,
+    /// Speed of card animations and bot moves, `1.0` being normal.
+    pub animation_speed: f32,
+    pub theme: String,
+    /// Name of the rule preset picked when the game starts.
+    pub preset: String,
+    pub bot_difficulty: Difficulty,
+    pub player_name: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            window_width: 800.,
+            window_height: 600.,
+            fullscreen: false,
+            animation_speed: 1.,
+            theme: String::from(DEFAULT_THEME),
+            preset: String::from(Rules::BUILT_IN[0].0),
+            bot_difficulty: Difficulty::Normal,
+            player_name: String::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings file, or returns the defaults if there is none yet.
+    /// Numbers out of range, e.g. after editing the file by hand, are brought
+    /// back into it.
+    pub fn load(ctx: &Context) -> Result<Settings, DurakError> {
+        if !ctx.fs.exists(SETTINGS_PATH) {
+            return Ok(Settings::default());
+        }
+        let mut text = String::new();
+        ctx.fs.open(SETTINGS_PATH)?.read_to_string(&mut text)?;
+        let settings: Settings = toml::from_str(&text)
+            .map_err(|error| DurakError::state(format!("{SETTINGS_PATH}: {error}")))?;
+        Ok(settings.clamped())
+    }
+
+    /// Clamps every number to its range, replacing those that aren't finite
+    /// with the default.
+    fn clamped(self) -> Settings {
+        let defaults = Settings::default();
+        let clamp = |value: f32, range: RangeInclusive<f32>, default: f32| {
+            if value.is_finite() {
+                value.clamp(*range.start(), *range.end())
+            } else {
+                default
+            }
+        };
+        Settings {
+            window_width: clamp(self.window_width, WINDOW_WIDTHS, defaults.window_width),
+            window_height: clamp(self.window_height, WINDOW_HEIGHTS, defaults.window_height),
+            animation_speed: clamp(
+                self.animation_speed,
+                ANIMATION_SPEEDS,
+                defaults.animation_speed,
+            ),
+            ..self
+        }
+    }
+
+    pub fn save(&self, ctx: &Context) -> Result<(), DurakError> {
+        let text = toml::to_string(self)
+            .map_err(|error| DurakError::state(format!("{SETTINGS_PATH}: {error}")))?;
+        ctx.fs.create(SETTINGS_PATH)?.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Resizes the window, or makes it fullscreen.
+    pub fn apply_window(&self, ctx: &mut Context) -> Result<(), DurakError> {
+        let fullscreen = if self.fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Windowed
+        };
+        ctx.gfx.set_mode(
+            WindowMode::default()
+                .dimensions(self.window_width, self.window_height)
+                .fullscreen_type(fullscreen),
+        )?;
+        Ok(())
+    }
+}
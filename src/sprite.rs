@@ -1,4 +1,7 @@
-use ggez::graphics::{DrawParam, Drawable, Image, Rect};
+use ggez::{
+    glam::{vec2, Vec2},
+    graphics::{DrawParam, Drawable, Image, Rect},
+};
 
 #[derive(Debug, Clone)]
 pub struct Sprite {
@@ -6,6 +9,16 @@ pub struct Sprite {
     pub image: Image,
 }
 
+impl Sprite {
+    /// Size of the sprite in pixels.
+    pub fn size(&self) -> Vec2 {
+        vec2(
+            self.src.w * self.image.width() as f32,
+            self.src.h * self.image.height() as f32,
+        )
+    }
+}
+
 impl Drawable for Sprite {
     fn draw(&self, canvas: &mut ggez::graphics::Canvas, param: impl Into<DrawParam>) {
         canvas.draw(&self.image, param.into().src(self.src))
@@ -15,6 +28,7 @@ impl Drawable for Sprite {
         &self,
         _gfx: &impl ggez::context::Has<ggez::graphics::GraphicsContext>,
     ) -> Option<ggez::graphics::Rect> {
-        todo!()
+        let size = self.size();
+        Some(Rect::new(0., 0., size.x, size.y))
     }
-}
\ No newline at end of file
+}
@@ -1,23 +1,257 @@
-use std::sync::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use ggez::{graphics::Image, Context};
+use ggez::{
+    audio::SoundData,
+    graphics::{FontData, Image, Rect},
+    Context,
+};
 
-use crate::error::DurakError;
+use crate::{error::DurakError, sprite::Sprite, theme::Theme};
 
-static CARD_IMAGE: Mutex<Option<Image>> = Mutex::new(None);
+/// Typed index into an [`Assets`] registry. Handles remember the registry
+/// that handed them out, and looking one up in any other finds nothing.
+pub struct Handle<T> {
+    /// Id of the [`Assets`] the handle came from.
+    owner: usize,
+    index: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.owner == other.owner && self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Handle")
+            .field(&self.owner)
+            .field(&self.index)
+            .finish()
+    }
+}
+
+pub struct Registry<T> {
+    items: Vec<T>,
+    names: HashMap<String, usize>,
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Registry {
+            items: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Registry<T> {
+    fn find(&self, owner: usize, name: &str) -> Option<Handle<T>> {
+        self.names.get(name).map(|&index| Handle {
+            owner,
+            index,
+            marker: PhantomData,
+        })
+    }
+
+    fn insert(&mut self, owner: usize, name: &str, item: T) -> Handle<T> {
+        if let Some(&index) = self.names.get(name) {
+            self.items[index] = item;
+            return Handle {
+                owner,
+                index,
+                marker: PhantomData,
+            };
+        }
+        self.items.push(item);
+        let index = self.items.len() - 1;
+        self.names.insert(name.to_string(), index);
+        Handle {
+            owner,
+            index,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// A grid of equally sized sprites cut from one image.
+#[derive(Debug, Clone)]
+pub struct Atlas {
+    pub image: Image,
+    pub columns: u32,
+    pub rows: u32,
+}
 
-pub fn load_card_image(ctx: &Context) -> Result<(), DurakError> {
-    let mut image_option = CARD_IMAGE.lock().map_err(|_e| "Lock Error")?;
-    if image_option.is_none() {
-        *image_option = Some(Image::from_path(ctx, "/cards.png")?);
+impl Atlas {
+    pub fn grid(image: Image, columns: u32, rows: u32) -> Self {
+        Atlas {
+            image,
+            columns,
+            rows,
+        }
     }
-    Ok(())
+
+    pub fn sprite(&self, column: u32, row: u32) -> Sprite {
+        let w = 1. / self.columns as f32;
+        let h = 1. / self.rows as f32;
+        Sprite {
+            src: Rect {
+                x: w * column as f32,
+                y: h * row as f32,
+                w,
+                h,
+            },
+            image: self.image.clone(),
+        }
+    }
+}
+
+/// Name under which a font was registered for text drawing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font(String);
+
+impl Font {
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+pub trait Asset: Sized {
+    fn registry(assets: &Assets) -> &Registry<Self>;
+    fn registry_mut(assets: &mut Assets) -> &mut Registry<Self>;
 }
 
-pub fn card_image() -> Result<Option<Image>, DurakError> {
-    Ok(CARD_IMAGE
-        .lock()
-        .map_err(|_e| "Resource Lock Error")?
-        .as_ref()
-        .cloned())
+/// Assets that can be read from a file in the resource directory. Assets
+/// made of others, like a theme and its sprite sheet, load those through
+/// `assets` so they are shared.
+pub trait Load: Asset {
+    fn load(ctx: &Context, assets: &mut Assets, path: &str) -> Result<Self, DurakError>;
+}
+
+macro_rules! asset {
+    ($type:ty, $field:ident) => {
+        impl Asset for $type {
+            fn registry(assets: &Assets) -> &Registry<Self> {
+                &assets.$field
+            }
+            fn registry_mut(assets: &mut Assets) -> &mut Registry<Self> {
+                &mut assets.$field
+            }
+        }
+    };
+}
+
+asset!(Image, images);
+asset!(Font, fonts);
+asset!(Atlas, atlases);
+asset!(SoundData, sounds);
+asset!(Theme, themes);
+
+fn missing(path: &str) -> impl FnOnce(ggez::GameError) -> DurakError + '_ {
+    move |error| DurakError::Asset {
+        name: path.to_string(),
+        source: Some(error),
+    }
+}
+
+impl Load for Image {
+    fn load(ctx: &Context, _assets: &mut Assets, path: &str) -> Result<Self, DurakError> {
+        Image::from_path(ctx, path).map_err(missing(path))
+    }
+}
+
+impl Load for SoundData {
+    fn load(ctx: &Context, _assets: &mut Assets, path: &str) -> Result<Self, DurakError> {
+        SoundData::new(ctx, path).map_err(missing(path))
+    }
+}
+
+/// Source of the ids telling registries apart.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Images, fonts, atlases, sounds and card themes used by the game, loaded
+/// the first time they are asked for.
+pub struct Assets {
+    id: usize,
+    images: Registry<Image>,
+    fonts: Registry<Font>,
+    atlases: Registry<Atlas>,
+    sounds: Registry<SoundData>,
+    themes: Registry<Theme>,
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Assets {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            images: Registry::default(),
+            fonts: Registry::default(),
+            atlases: Registry::default(),
+            sounds: Registry::default(),
+            themes: Registry::default(),
+        }
+    }
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn load<T: Load>(&mut self, ctx: &Context, path: &str) -> Result<Handle<T>, DurakError> {
+        if let Some(handle) = self.find(path) {
+            return Ok(handle);
+        }
+        let asset = T::load(ctx, self, path)?;
+        Ok(self.insert(path, asset))
+    }
+
+    pub fn insert<T: Asset>(&mut self, name: &str, asset: T) -> Handle<T> {
+        let id = self.id;
+        T::registry_mut(self).insert(id, name, asset)
+    }
+
+    pub fn find<T: Asset>(&self, name: &str) -> Option<Handle<T>> {
+        T::registry(self).find(self.id, name)
+    }
+
+    /// The asset behind `handle`, or `None` if the handle came from another
+    /// registry.
+    pub fn get<T: Asset>(&self, handle: Handle<T>) -> Option<&T> {
+        if handle.owner != self.id {
+            return None;
+        }
+        T::registry(self).items.get(handle.index)
+    }
+
+    /// Loads a font and makes it available to text drawing under `name`.
+    pub fn add_font(
+        &mut self,
+        ctx: &mut Context,
+        name: &str,
+        path: &str,
+    ) -> Result<Handle<Font>, DurakError> {
+        if let Some(handle) = self.find(path) {
+            return Ok(handle);
+        }
+        let data = FontData::from_path(ctx, path).map_err(missing(path))?;
+        ctx.gfx.add_font(name, data);
+        Ok(self.insert(path, Font(name.to_string())))
+    }
 }
@@ -0,0 +1,160 @@
+use std::{collections::HashMap, io::Read};
+
+use ggez::{
+    graphics::{Image, Rect},
+    Context,
+};
+use serde::Deserialize;
+
+use crate::{
+    card::{Rank, Suit},
+    error::DurakError,
+    rules::DeckSize,
+    sprite::Sprite,
+    storage::{Assets, Atlas, Load},
+};
+
+pub const DEFAULT_THEME: &str = "/themes/classic.toml";
+
+/// Layout of a card set as written in a theme file. Cards are laid out on a
+/// `grid`, listed one by one in `cards` with pixel rects, or both, in which
+/// case the listed cards add to those of the grid.
+#[derive(Debug, Deserialize)]
+pub struct ThemeDescriptor {
+    pub name: String,
+    pub image: String,
+    pub grid: Option<GridLayout>,
+    #[serde(default)]
+    pub cards: Vec<CardRect>,
+    /// Pixel rects of card backs, added to those of the grid.
+    #[serde(default)]
+    pub backs: Vec<[f32; 4]>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GridLayout {
+    pub columns: u32,
+    pub rows: u32,
+    /// Suit of each row, from the top.
+    pub suits: Vec<Suit>,
+    /// Rank of each column, from the left.
+    pub ranks: Vec<Rank>,
+    /// `[column, row]` of each card back.
+    pub backs: Vec<[u32; 2]>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CardRect {
+    pub suit: Suit,
+    pub rank: Rank,
+    pub rect: [f32; 4],
+}
+
+/// Sprites for every card face and back of a card set.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    fronts: HashMap<(Suit, Rank), Sprite>,
+    backs: Vec<Sprite>,
+}
+
+impl Theme {
+    pub fn from_descriptor(descriptor: ThemeDescriptor, image: Image) -> Result<Theme, DurakError> {
+        let mut fronts = HashMap::new();
+        let mut backs = Vec::new();
+        if let Some(grid) = descriptor.grid {
+            let atlas = Atlas::grid(image.clone(), grid.columns, grid.rows);
+            for (row, suit) in grid.suits.iter().enumerate() {
+                for (column, rank) in grid.ranks.iter().enumerate() {
+                    fronts.insert((*suit, *rank), atlas.sprite(column as u32, row as u32));
+                }
+            }
+            backs.extend(
+                grid.backs
+                    .iter()
+                    .map(|[column, row]| atlas.sprite(*column, *row)),
+            );
+        }
+
+        let sprite = |[x, y, w, h]: [f32; 4]| {
+            let (width, height) = (image.width() as f32, image.height() as f32);
+            Sprite {
+                src: Rect::new(x / width, y / height, w / width, h / height),
+                image: image.clone(),
+            }
+        };
+        for card in descriptor.cards {
+            fronts.insert((card.suit, card.rank), sprite(card.rect));
+        }
+        backs.extend(descriptor.backs.into_iter().map(sprite));
+
+        if fronts.is_empty() || backs.is_empty() {
+            return Err(DurakError::asset(format!(
+                "{} (theme needs at least one card face and one back)",
+                descriptor.image
+            )));
+        }
+
+        Ok(Theme {
+            name: descriptor.name,
+            fronts,
+            backs,
+        })
+    }
+
+    pub fn front(&self, suit: Suit, rank: Rank) -> Option<Sprite> {
+        self.fronts.get(&(suit, rank)).cloned()
+    }
+
+    /// Whether the theme has a face for every card of a deck of `size`.
+    pub fn supports(&self, size: DeckSize) -> bool {
+        Suit::ALL.into_iter().all(|suit| {
+            size.ranks()
+                .all(|rank| self.fronts.contains_key(&(suit, rank)))
+        })
+    }
+
+    /// Back used for the `deck_id`th deck. Themes with fewer backs than
+    /// decks start over from the first one.
+    pub fn back(&self, deck_id: usize) -> Sprite {
+        self.backs[deck_id % self.backs.len()].clone()
+    }
+
+    /// Paths of all theme files in `/themes`.
+    pub fn available(ctx: &Context) -> Vec<String> {
+        let mut themes = ctx
+            .fs
+            .read_dir("/themes")
+            .map(|paths| {
+                paths
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                    .filter_map(|path| path.to_str().map(|path| path.replace('\\', "/")))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        themes.sort();
+        themes
+    }
+}
+
+impl Load for Theme {
+    fn load(ctx: &Context, assets: &mut Assets, path: &str) -> Result<Self, DurakError> {
+        let mut text = String::new();
+        ctx.fs
+            .open(path)
+            .map_err(|error| DurakError::Asset {
+                name: path.to_string(),
+                source: Some(error),
+            })?
+            .read_to_string(&mut text)?;
+        let descriptor: ThemeDescriptor = toml::from_str(&text)
+            .map_err(|error| DurakError::asset(format!("{path} ({error})")))?;
+        let image = assets.load::<Image>(ctx, &descriptor.image)?;
+        let image = assets
+            .get(image)
+            .cloned()
+            .ok_or_else(|| DurakError::asset(&descriptor.image))?;
+
+        Theme::from_descriptor(descriptor, image)
+    }
+}
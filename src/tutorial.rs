@@ -0,0 +1,403 @@
+use std::time::Duration;
+
+use ggegui::{
+    egui::{Area, Button, Color32},
+    Gui,
+};
+use ggez::{
+    graphics::{Canvas, Color, DrawParam},
+    Context,
+};
+
+use crate::{
+    bot,
+    card::{Rank, Suit},
+    engine::{Action, Engine},
+    error::DurakError,
+    game::DurakState,
+    game_scenes::{DurakSceneTransition, MainMenu},
+    rules::{CardId, Rules},
+    scenes::{NewScene, Scene, SceneResult, SceneSwitch},
+};
+
+/// The player taking the tutorial.
+const YOU: usize = 0;
+/// The bot playing against them.
+const TEACHER: usize = 1;
+/// Pause before the teacher makes a move.
+const TEACHER_DELAY: Duration = Duration::from_millis(600);
+
+/// How a move made during a lesson relates to what the lesson teaches.
+enum Progress {
+    /// Legal, but not what the lesson is about. The move is not played.
+    Wrong(&'static str),
+    /// Played, the lesson goes on.
+    Next,
+    /// Played, the lesson is complete.
+    Done,
+}
+
+/// One step of the tutorial, played on a scripted deal.
+struct Lesson {
+    title: &'static str,
+    text: &'static str,
+    /// The face up card at the bottom of the deck.
+    trump: (Rank, Suit),
+    you: [(Rank, Suit); 6],
+    teacher: [(Rank, Suit); 6],
+    rules: Rules,
+    check: fn(&Engine, Action) -> Progress,
+}
+
+const LESSONS: [Lesson; 5] = [
+    Lesson {
+        title: "Attacking",
+        text: "The player with the lowest trump attacks first. That's you: \
+               lead with any card. A low card that isn't a trump is usually best.",
+        trump: (Rank::Ten, Suit::Spades),
+        you: [
+            (Rank::Six, Suit::Spades),
+            (Rank::Seven, Suit::Hearts),
+            (Rank::Seven, Suit::Clubs),
+            (Rank::Nine, Suit::Diamonds),
+            (Rank::Jack, Suit::Clubs),
+            (Rank::King, Suit::Hearts),
+        ],
+        teacher: [
+            (Rank::Eight, Suit::Spades),
+            (Rank::Ten, Suit::Hearts),
+            (Rank::Queen, Suit::Diamonds),
+            (Rank::Ace, Suit::Clubs),
+            (Rank::Eight, Suit::Diamonds),
+            (Rank::Nine, Suit::Hearts),
+        ],
+        rules: Rules::CLASSIC,
+        check: |_, action| match action {
+            Action::Attack(_) => Progress::Done,
+            _ => Progress::Wrong("Click a card to attack with it"),
+        },
+    },
+    Lesson {
+        title: "Defending with trumps",
+        text: "Now you defend. Cover the attack with a higher card of the same \
+               suit, or with any trump. You have no clubs, so use a trump.",
+        trump: (Rank::Ten, Suit::Diamonds),
+        you: [
+            (Rank::Seven, Suit::Diamonds),
+            (Rank::Eight, Suit::Hearts),
+            (Rank::Ten, Suit::Spades),
+            (Rank::Six, Suit::Hearts),
+            (Rank::Seven, Suit::Spades),
+            (Rank::Eight, Suit::Spades),
+        ],
+        teacher: [
+            (Rank::Six, Suit::Diamonds),
+            (Rank::Nine, Suit::Clubs),
+            (Rank::Jack, Suit::Clubs),
+            (Rank::Queen, Suit::Hearts),
+            (Rank::King, Suit::Spades),
+            (Rank::Ace, Suit::Hearts),
+        ],
+        rules: Rules::CLASSIC,
+        check: |engine, action| match action {
+            Action::Defend(_, card) if card.suit == engine.trump() => Progress::Done,
+            _ => Progress::Wrong("Cover the attack with your trump"),
+        },
+    },
+    Lesson {
+        title: "Throwing in",
+        text: "Once a bout has started, attackers may add cards of any rank \
+               already on the table. Lead with an eight, then throw in the other.",
+        trump: (Rank::Nine, Suit::Clubs),
+        you: [
+            (Rank::Six, Suit::Clubs),
+            (Rank::Eight, Suit::Hearts),
+            (Rank::Eight, Suit::Spades),
+            (Rank::Ten, Suit::Diamonds),
+            (Rank::Queen, Suit::Hearts),
+            (Rank::King, Suit::Spades),
+        ],
+        teacher: [
+            (Rank::Nine, Suit::Hearts),
+            (Rank::Jack, Suit::Spades),
+            (Rank::Seven, Suit::Clubs),
+            (Rank::Ten, Suit::Spades),
+            (Rank::Queen, Suit::Diamonds),
+            (Rank::Ace, Suit::Diamonds),
+        ],
+        rules: Rules::CLASSIC,
+        check: |engine, action| match action {
+            Action::Attack(card) if card.rank == Rank::Eight && engine.table().is_empty() => {
+                Progress::Next
+            }
+            Action::Attack(_) if !engine.table().is_empty() => Progress::Done,
+            _ => Progress::Wrong("Lead with an eight, then throw in the other"),
+        },
+    },
+    Lesson {
+        title: "Taking",
+        text: "Sometimes you can't beat an attack. Then you take every card on \
+               the table and lose your turn to attack. Press Take.",
+        trump: (Rank::Ten, Suit::Hearts),
+        you: [
+            (Rank::Seven, Suit::Spades),
+            (Rank::Nine, Suit::Spades),
+            (Rank::Ten, Suit::Clubs),
+            (Rank::Eight, Suit::Diamonds),
+            (Rank::Jack, Suit::Diamonds),
+            (Rank::Seven, Suit::Clubs),
+        ],
+        teacher: [
+            (Rank::Six, Suit::Hearts),
+            (Rank::Queen, Suit::Spades),
+            (Rank::Ace, Suit::Spades),
+            (Rank::Ace, Suit::Clubs),
+            (Rank::Ace, Suit::Diamonds),
+            (Rank::King, Suit::Clubs),
+        ],
+        rules: Rules::CLASSIC,
+        check: |_, action| match action {
+            Action::Take => Progress::Done,
+            _ => Progress::Wrong("You can't beat that, press Take"),
+        },
+    },
+    Lesson {
+        title: "Transferring",
+        text: "In transfer Durak, before covering anything you may add a card \
+               of the same rank and pass the attack on to the next player. \
+               Transfer the seven with yours.",
+        trump: (Rank::Nine, Suit::Spades),
+        you: [
+            (Rank::Seven, Suit::Hearts),
+            (Rank::Eight, Suit::Clubs),
+            (Rank::Nine, Suit::Diamonds),
+            (Rank::King, Suit::Diamonds),
+            (Rank::Ten, Suit::Hearts),
+            (Rank::Jack, Suit::Clubs),
+        ],
+        teacher: [
+            (Rank::Six, Suit::Spades),
+            (Rank::Seven, Suit::Diamonds),
+            (Rank::Ten, Suit::Clubs),
+            (Rank::Jack, Suit::Hearts),
+            (Rank::Queen, Suit::Clubs),
+            (Rank::Ace, Suit::Hearts),
+        ],
+        rules: Rules {
+            transfers: true,
+            ..Rules::CLASSIC
+        },
+        check: |_, action| match action {
+            Action::Transfer(_) => Progress::Done,
+            _ => Progress::Wrong("Play your seven to transfer instead of covering"),
+        },
+    },
+];
+
+impl Lesson {
+    /// Deck that deals exactly the scripted hands, with the trump card left
+    /// at the bottom.
+    fn talon(&self) -> Vec<CardId> {
+        let card = |(rank, suit): (Rank, Suit)| CardId {
+            suit,
+            rank,
+            deck_id: 0,
+        };
+        let dealt = self
+            .you
+            .iter()
+            .zip(&self.teacher)
+            .flat_map(|(you, teacher)| [card(*you), card(*teacher)]);
+
+        [card(self.trump)].into_iter().chain(dealt.rev()).collect()
+    }
+
+    fn start(&self) -> Result<Engine, DurakError> {
+        let (engine, _) = Engine::new(self.talon(), 2, None, self.rules)?;
+        Ok(engine)
+    }
+}
+
+/// Walks a new player through the rules on scripted deals against a bot.
+/// Every move is checked by the rules engine, and then against what the
+/// current lesson asks for.
+pub struct Tutorial {
+    state: DurakState,
+    lesson: usize,
+    engine: Engine,
+    message: Option<String>,
+    done: bool,
+    teacher_timer: Duration,
+}
+
+impl Tutorial {
+    fn start_lesson(&mut self, lesson: usize) -> Result<(), DurakError> {
+        self.lesson = lesson;
+        self.engine = LESSONS[lesson].start()?;
+        self.message = None;
+        self.done = false;
+        self.teacher_timer = Duration::ZERO;
+        Ok(())
+    }
+
+    fn try_move(&mut self, action: Action) -> Result<(), DurakError> {
+        if let Err(DurakError::Rules(reason)) = self.engine.check(YOU, action) {
+            self.message = Some(format!("Not allowed: {reason}"));
+            return Ok(());
+        }
+        match (LESSONS[self.lesson].check)(&self.engine, action) {
+            Progress::Wrong(hint) => self.message = Some(String::from(hint)),
+            progress => {
+                self.engine.apply(YOU, action)?;
+                self.done = matches!(progress, Progress::Done);
+                self.message = None;
+                self.teacher_timer = Duration::ZERO;
+            }
+        }
+        Ok(())
+    }
+
+    /// Plays the card or button picked on the screen, then lets the teacher
+    /// answer once it has waited long enough.
+    fn respond(
+        &mut self,
+        clicked: Option<CardId>,
+        take: bool,
+        pass: bool,
+        delta: Duration,
+    ) -> Result<(), DurakError> {
+        if let Some(card) = clicked {
+            let action = self.engine.play_card(YOU, card, None);
+            self.try_move(action)?;
+        } else if take {
+            self.try_move(Action::Take)?;
+        } else if pass {
+            self.try_move(Action::Pass)?;
+        }
+
+        self.teacher_timer += delta;
+        if !self.done && self.teacher_timer >= TEACHER_DELAY {
+            if let Some(action) = bot::choose(&self.engine, TEACHER) {
+                self.engine.apply(TEACHER, action)?;
+                self.teacher_timer = Duration::ZERO;
+            }
+        }
+        Ok(())
+    }
+
+    fn table(&self) -> String {
+        if self.engine.table().is_empty() {
+            return String::from("Table: empty");
+        }
+        let stacks: Vec<String> = self
+            .engine
+            .table()
+            .iter()
+            .map(|(attack, cover)| match cover {
+                Some(cover) => format!("{attack}/{cover}"),
+                None => attack.to_string(),
+            })
+            .collect();
+        format!("Table: {}", stacks.join("  "))
+    }
+}
+
+impl Scene for Tutorial {
+    type State = DurakState;
+    type Error = DurakError;
+
+    fn update(mut self: Box<Self>, gui: &mut Gui, ctx: &mut Context) -> SceneResult<Self> {
+        let lesson = &LESSONS[self.lesson];
+        let last = self.lesson + 1 == LESSONS.len();
+        let mut clicked = None;
+        let (take, pass, next, menu) = Area::new("id")
+            .show(&gui.ctx(), |ui| {
+                ui.heading(format!(
+                    "Lesson {} of {}: {}",
+                    self.lesson + 1,
+                    LESSONS.len(),
+                    lesson.title
+                ));
+                ui.label(lesson.text);
+                ui.label(format!("Trump: {}", self.engine.trump().symbol()));
+                ui.label(format!(
+                    "Teacher holds {} cards",
+                    self.engine.hand(TEACHER).len()
+                ));
+                ui.label(self.table());
+                ui.horizontal(|ui| {
+                    for card in self.engine.hand(YOU) {
+                        if ui
+                            .add_enabled(!self.done, Button::new(card.to_string()))
+                            .clicked()
+                        {
+                            clicked = Some(*card);
+                        }
+                    }
+                });
+                let buttons = ui
+                    .horizontal(|ui| {
+                        (
+                            ui.add_enabled(!self.done, Button::new("Take")).clicked(),
+                            ui.add_enabled(!self.done, Button::new("Pass")).clicked(),
+                        )
+                    })
+                    .inner;
+                if let Some(message) = &self.message {
+                    ui.colored_label(Color32::LIGHT_RED, message);
+                }
+                let next = self.done
+                    && ui
+                        .button(if last { "Finish" } else { "Next lesson" })
+                        .clicked();
+                if self.done {
+                    ui.colored_label(Color32::LIGHT_GREEN, "Well done!");
+                }
+                (buttons.0, buttons.1, next, ui.button("Main Menu").clicked())
+            })
+            .inner;
+        gui.update(ctx);
+
+        if menu || (next && last) {
+            return <Self as DurakSceneTransition<MainMenu>>::switch(self, ctx);
+        }
+        let result = if next {
+            self.start_lesson(self.lesson + 1)
+        } else {
+            self.respond(clicked, take, pass, ctx.time.delta())
+        };
+        if let Err(error) = result {
+            return Ok(SceneSwitch::Failed(self.take_state(), error));
+        }
+
+        Ok(SceneSwitch::Stay(self))
+    }
+
+    fn draw(&self, gui: &Gui, ctx: &mut Context) -> Result<(), DurakError> {
+        let mut canvas = Canvas::from_frame(ctx, Color::from([0.1, 0.3, 0.2, 1.0]));
+
+        gui.draw(&mut canvas, DrawParam::new());
+        canvas.finish(ctx)?;
+
+        Ok(())
+    }
+
+    fn new(state: DurakState, _ctx: &Context) -> NewScene<Tutorial> {
+        let engine = match LESSONS[0].start() {
+            Ok(engine) => engine,
+            Err(error) => return Err((error, state)),
+        };
+        Ok(Tutorial {
+            state,
+            lesson: 0,
+            engine,
+            message: None,
+            done: false,
+            teacher_timer: Duration::ZERO,
+        })
+    }
+
+    fn take_state(self: Box<Self>) -> DurakState {
+        self.state
+    }
+}
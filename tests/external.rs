@@ -0,0 +1,212 @@
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    process,
+    time::{Duration, Instant},
+};
+
+use durak_rust::{
+    engine::{Action, Engine},
+    error::DurakError,
+    external::{split_command, ExternalBot},
+    rules::{self, DeckSize, Rules},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Time the bots get in these tests, short so the timeout test is quick.
+const TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Answers every request with its first legal move, after an answer to a
+/// request that was never sent.
+const FIRST_LEGAL: &str = r#"#!/bin/sh
+while read -r line; do
+    id=$(printf '%s\n' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+    move=$(printf '%s\n' "$line" | sed 's/.*"legal":\[{\([^}]*\)}.*/\1/')
+    echo '{"id":999,"action":"take"}'
+    printf '{"id":%s,%s}\n' "$id" "$move"
+done
+"#;
+
+/// Takes, whether or not that is allowed.
+const ALWAYS_TAKES: &str = r#"#!/bin/sh
+while read -r line; do
+    id=$(printf '%s\n' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+    printf '{"id":%s,"action":"take"}\n' "$id"
+done
+"#;
+
+const GARBAGE: &str = "#!/bin/sh\nwhile read -r line; do echo 'no move here'; done\n";
+
+const SILENT: &str = "#!/bin/sh\nexec sleep 10\n";
+
+/// Answers every request with its first legal move after thinking a while.
+const SLOW: &str = r#"#!/bin/sh
+while read -r line; do
+    id=$(printf '%s\n' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+    move=$(printf '%s\n' "$line" | sed 's/.*"legal":\[{\([^}]*\)}.*/\1/')
+    sleep 0.1
+    printf '{"id":%s,%s}\n' "$id" "$move"
+done
+"#;
+
+const QUITS: &str = "#!/bin/sh\nexit 0\n";
+
+/// A bot script in a directory of its own, with a space in its name, which
+/// is removed along with it.
+struct Script {
+    dir: PathBuf,
+    /// Command line starting the script.
+    command: String,
+}
+
+impl Script {
+    fn new(name: &str, script: &str) -> Script {
+        let dir = env::temp_dir().join(format!("durak bots {} {name}", process::id()));
+        fs::create_dir_all(&dir).expect("a directory for the bot");
+        let path = dir.join(name);
+        fs::write(&path, script).expect("the bot script");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("an executable bot");
+        let command = format!("'{}'", path.display());
+        Script { dir, command }
+    }
+
+    fn spawn(&self) -> ExternalBot {
+        ExternalBot::spawn(&self.command, TIMEOUT).expect("spawn")
+    }
+}
+
+impl Drop for Script {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A two player game, the attacker to lead.
+fn game() -> Engine {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut talon = rules::card_ids(DeckSize::Standard, 1);
+    rules::shuffle(&mut talon, &mut rng);
+    let (engine, _) = Engine::new(talon, 2, None, Rules::default()).expect("a valid deal");
+    engine
+}
+
+fn assert_protocol_error(result: Result<Action, DurakError>) {
+    assert!(
+        matches!(result, Err(DurakError::Protocol(_))),
+        "expected a protocol error, got {result:?}"
+    );
+}
+
+#[test]
+fn plays_the_move_it_is_sent() {
+    let mut engine = game();
+    let script = Script::new("first-legal", FIRST_LEGAL);
+    let mut bot = script.spawn();
+    for _ in 0..4 {
+        let player = (0..engine.players())
+            .find(|player| engine.needs_to_act(*player))
+            .expect("someone has to act");
+        let action = bot.play(&engine, player).expect("a move");
+        assert_eq!(action, engine.legal_actions(player)[0]);
+        engine.apply(player, action).expect("a legal move");
+    }
+}
+
+#[test]
+fn illegal_moves_are_refused() {
+    let engine = game();
+    let script = Script::new("always-takes", ALWAYS_TAKES);
+    let mut bot = script.spawn();
+    assert_protocol_error(bot.play(&engine, engine.attacker()));
+}
+
+#[test]
+fn garbage_is_refused() {
+    let engine = game();
+    let script = Script::new("garbage", GARBAGE);
+    let mut bot = script.spawn();
+    assert_protocol_error(bot.play(&engine, engine.attacker()));
+}
+
+#[test]
+fn silent_programs_run_out_of_time() {
+    let engine = game();
+    let script = Script::new("silent", SILENT);
+    let mut bot = script.spawn();
+    let start = Instant::now();
+    assert_protocol_error(bot.play(&engine, engine.attacker()));
+    assert!(start.elapsed() >= TIMEOUT);
+}
+
+#[test]
+fn programs_that_quit_are_noticed() {
+    let engine = game();
+    let script = Script::new("quits", QUITS);
+    let mut bot = script.spawn();
+    assert_protocol_error(bot.play(&engine, engine.attacker()));
+    assert_protocol_error(bot.play(&engine, engine.attacker()));
+}
+
+#[test]
+fn answers_for_an_earlier_position_are_ignored() {
+    let engine = game();
+    let attacker = engine.attacker();
+    let defender = engine.defender();
+    let position = |card: usize| {
+        let mut engine = engine.clone();
+        let action = engine.legal_actions(attacker)[card];
+        engine.apply(attacker, action).expect("a legal attack");
+        engine
+    };
+    // The same number of moves into the game, but another attack to answer.
+    let (first, second) = (position(0), position(1));
+    let expected = second.legal_actions(defender)[0];
+    assert_ne!(first.legal_actions(defender)[0], expected);
+
+    let script = Script::new("slow", SLOW);
+    let mut bot = script.spawn();
+    assert_eq!(bot.poll(&first, defender).expect("no error yet"), None);
+    assert_eq!(bot.play(&second, defender).expect("a move"), expected);
+}
+
+#[test]
+fn programs_that_stop_reading_hold_nothing_up() {
+    let mut engine = game();
+    let script = Script::new("silent", SILENT);
+    let mut bot = script.spawn();
+    // Far more requests than the pipe to the program holds.
+    let start = Instant::now();
+    for _ in 0..500 {
+        let player = (0..engine.players())
+            .find(|player| engine.needs_to_act(*player))
+            .expect("someone has to act");
+        assert_eq!(bot.poll(&engine, player).expect("no error yet"), None);
+        let action = engine.legal_actions(player)[0];
+        engine.apply(player, action).expect("a legal move");
+        if engine.outcome().is_some() {
+            engine = game();
+        }
+    }
+    assert!(start.elapsed() < TIMEOUT);
+    assert_protocol_error(bot.play(&engine, engine.attacker()));
+}
+
+#[test]
+fn commands_split_like_in_a_shell() {
+    let words = split_command(r#""/home/me/My Bots/bot" --level 'very hard' a\ b "x\"y\z""#);
+    assert_eq!(
+        words.expect("a valid command"),
+        [
+            "/home/me/My Bots/bot",
+            "--level",
+            "very hard",
+            "a b",
+            r#"x"y\z"#
+        ]
+    );
+    assert!(split_command("bot 'unfinished").is_err());
+    assert!(split_command("   ").expect("a valid command").is_empty());
+}
@@ -0,0 +1,85 @@
+use std::{net::TcpListener, thread};
+
+use durak_rust::{
+    bot::{self, Difficulty},
+    engine::Engine,
+    error::DurakError,
+    network::{self, Connection, Message},
+    rules::{self, DeckSize, Rules},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// A two player game, the attacker to lead.
+fn game(rng: &mut StdRng) -> Engine {
+    let mut talon = rules::card_ids(DeckSize::Standard, 1);
+    rules::shuffle(&mut talon, rng);
+    let (engine, _) = Engine::new(talon, 2, None, Rules::default()).expect("a valid deal");
+    engine
+}
+
+/// Listens on a free port of this machine.
+fn listen() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("a free port");
+    let address = listener.local_addr().expect("an address").to_string();
+    (listener, address)
+}
+
+#[test]
+fn guests_play_over_the_network() {
+    let (listener, address) = listen();
+    // Plays the first legal move until told the result.
+    let guest = thread::spawn(move || {
+        let mut connection = Connection::join(&address, "Guest").expect("joined");
+        let mut moves = 0;
+        loop {
+            match connection.wait().expect("a message from the host") {
+                Message::Request(request) => {
+                    let action = request.legal[0].clone();
+                    connection.reply(request.id, action).expect("a reply");
+                    moves += 1;
+                }
+                Message::Finished(finished) => return (moves, finished.result),
+            }
+        }
+    });
+
+    let (name, mut remote) = network::accept(&listener).expect("a guest");
+    assert_eq!(name, "Guest");
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut engine = game(&mut rng);
+    let mut moves = 0;
+    while engine.outcome().is_none() {
+        let (player, action) = if engine.needs_to_act(1) {
+            let action = remote.play(&engine, 1).expect("a move");
+            assert_eq!(action, engine.legal_actions(1)[0]);
+            moves += 1;
+            (1, action)
+        } else {
+            let action = bot::play(&engine, 0, Difficulty::Normal, &mut rng).expect("a move");
+            (0, action)
+        };
+        engine.apply(player, action).expect("a legal move");
+    }
+    remote.finish("Guest is the durak");
+    drop(remote);
+
+    let (played, result) = guest.join().expect("the guest finished");
+    assert_eq!(played, moves);
+    assert_eq!(result, "Guest is the durak");
+}
+
+#[test]
+fn guests_leaving_are_noticed() {
+    let (listener, address) = listen();
+    let guest = thread::spawn(move || drop(Connection::join(&address, "Guest")));
+    let (_, mut remote) = network::accept(&listener).expect("a guest");
+    guest.join().expect("the guest left");
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let engine = game(&mut rng);
+    let result = remote.play(&engine, engine.attacker());
+    assert!(
+        matches!(result, Err(DurakError::Protocol(_))),
+        "expected a protocol error, got {result:?}"
+    );
+}
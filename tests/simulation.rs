@@ -0,0 +1,473 @@
+use std::collections::HashSet;
+
+use durak_rust::{
+    bot::{self, Difficulty},
+    card::{Rank, Suit},
+    engine::{Action, Engine, Outcome},
+    error::DurakError,
+    presets::Presets,
+    replay::Replay,
+    rules::{self, CardId, DeckSize, Rules},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+/// Moves after which a game is taken to be stuck.
+const MOVE_LIMIT: usize = 10_000;
+
+/// Shuffles a standard deck with `rng` and deals it.
+fn deal(rng: &mut StdRng, players: usize, teams: bool, rules: Rules) -> Engine {
+    deal_decks(rng, 1, players, teams, rules)
+}
+
+/// Shuffles `decks` standard decks together with `rng` and deals them.
+fn deal_decks(rng: &mut StdRng, decks: usize, players: usize, teams: bool, rules: Rules) -> Engine {
+    let mut talon = rules::card_ids(DeckSize::Standard, decks);
+    rules::shuffle(&mut talon, rng);
+    let teams = teams.then(|| (0..players).map(|player| player % 2).collect());
+    let (engine, _) = Engine::new(talon, players, teams, rules).expect("a valid deal");
+    engine
+}
+
+/// Every card wherever it is: hands, deck, table and discard pile.
+fn all_cards(engine: &Engine) -> Vec<CardId> {
+    let mut cards: Vec<CardId> = (0..engine.players())
+        .flat_map(|player| engine.hand(player).iter().copied())
+        .collect();
+    cards.extend(engine.talon());
+    cards.extend(
+        engine
+            .table()
+            .iter()
+            .flat_map(|(attack, cover)| [Some(*attack), *cover])
+            .flatten(),
+    );
+    cards.extend(engine.discard());
+    cards
+}
+
+/// Checks what has to hold after every move.
+fn check_invariants(engine: &Engine) {
+    let cards = all_cards(engine);
+    let dealt = engine.deal().len();
+    assert_eq!(cards.len(), dealt, "cards went missing or appeared");
+    // Copies of a card from two decks only differ in `deck_id`.
+    let key = |card: &CardId| (card.suit, card.rank, card.deck_id);
+    let unique: HashSet<(Suit, Rank, usize)> = cards.iter().map(key).collect();
+    assert_eq!(unique.len(), dealt, "a card is in two places at once");
+    let deal: HashSet<(Suit, Rank, usize)> = engine.deal().iter().map(key).collect();
+    assert_eq!(unique, deal, "a card that wasn't dealt turned up");
+
+    for (attack, cover) in engine.table() {
+        if let Some(cover) = cover {
+            assert!(
+                rules::beats(*attack, *cover, engine.trump()),
+                "{cover} was accepted on {attack}"
+            );
+        }
+    }
+    for player in 0..engine.players() {
+        if engine.is_out(player) {
+            assert!(
+                engine.hand(player).is_empty(),
+                "player {player} is out holding cards"
+            );
+        }
+    }
+}
+
+/// Tries every card of the defender on every uncovered attack it doesn't
+/// beat and checks the engine turns them all down without changing anything.
+fn check_illegal_defences(engine: &Engine) {
+    let defender = engine.defender();
+    let before = format!("{engine:?}");
+    for (stack, (attack, cover)) in engine.table().iter().enumerate() {
+        if cover.is_some() {
+            continue;
+        }
+        for card in engine.hand(defender) {
+            if rules::beats(*attack, *card, engine.trump()) {
+                continue;
+            }
+            let mut copy = engine.clone();
+            let result = copy.apply(defender, Action::Defend(stack, *card));
+            assert!(
+                matches!(result, Err(DurakError::Rules(_))),
+                "{card} was accepted on {attack}"
+            );
+            assert_eq!(
+                format!("{copy:?}"),
+                before,
+                "a rejected move changed the game"
+            );
+        }
+    }
+}
+
+/// Plays a random legal move of a random player who has one.
+fn random_move(engine: &Engine, rng: &mut StdRng) -> (usize, Action) {
+    let moves: Vec<(usize, Action)> = (0..engine.players())
+        .flat_map(|player| {
+            engine
+                .legal_actions(player)
+                .into_iter()
+                .map(move |action| (player, action))
+        })
+        .collect();
+    // Prefer the players holding up the game, so passing doesn't go on forever.
+    let urgent: Vec<(usize, Action)> = moves
+        .iter()
+        .copied()
+        .filter(|(player, _)| engine.needs_to_act(*player))
+        .collect();
+    let moves = if !urgent.is_empty() && rng.gen_bool(0.8) {
+        urgent
+    } else {
+        moves
+    };
+    *moves.choose(rng).expect("someone can move")
+}
+
+/// Plays `engine` to the end with `next` picking the moves, checking the
+/// invariants after every one.
+fn play_out(engine: &mut Engine, mut next: impl FnMut(&Engine) -> (usize, Action)) -> Outcome {
+    for _ in 0..MOVE_LIMIT {
+        if let Some(outcome) = engine.outcome() {
+            return outcome;
+        }
+        check_illegal_defences(engine);
+        let (player, action) = next(engine);
+        engine
+            .apply(player, action)
+            .unwrap_or_else(|error| panic!("{action:?} of player {player}: {error}"));
+        check_invariants(engine);
+    }
+    panic!("the game didn't end within {MOVE_LIMIT} moves");
+}
+
+/// Rules of every preset with draws on and off.
+fn all_rules() -> Vec<Rules> {
+    Rules::BUILT_IN
+        .iter()
+        .flat_map(|(_, rules)| {
+            [
+                *rules,
+                Rules {
+                    draws: false,
+                    ..*rules
+                },
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn random_games_keep_every_card() {
+    for (i, rules) in all_rules().into_iter().enumerate() {
+        for seed in 0..40 {
+            let mut rng = StdRng::seed_from_u64(seed * 100 + i as u64);
+            let players = 2 + seed as usize % 5;
+            let teams = players >= 4 && players.is_multiple_of(2) && seed.is_multiple_of(2);
+            let mut engine = deal(&mut rng, players, teams, rules);
+            check_invariants(&engine);
+            play_out(&mut engine, |engine| random_move(engine, &mut rng));
+        }
+    }
+}
+
+#[test]
+fn two_deck_games_keep_every_card() {
+    for seed in 0..14 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let players = 2 + seed as usize % 7;
+        let teams = players >= 4 && players.is_multiple_of(2) && seed.is_multiple_of(3);
+        let rules = all_rules()[seed as usize % all_rules().len()];
+        let mut engine = deal_decks(&mut rng, 2, players, teams, rules);
+        check_invariants(&engine);
+        play_out(&mut engine, |engine| random_move(engine, &mut rng));
+    }
+}
+
+#[test]
+fn bot_games_finish() {
+    let difficulties = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+    for (i, rules) in all_rules().into_iter().enumerate() {
+        for seed in 0..30 {
+            let mut rng = StdRng::seed_from_u64(seed * 100 + i as u64);
+            let players = 2 + seed as usize % 5;
+            let mut engine = deal(&mut rng, players, false, rules);
+            play_out(&mut engine, |engine| {
+                (0..engine.players())
+                    .find_map(|player| {
+                        let difficulty = difficulties[player % difficulties.len()];
+                        Some((player, bot::play(engine, player, difficulty, &mut rng)?))
+                    })
+                    .expect("a bot has to act")
+            });
+        }
+    }
+}
+
+#[test]
+fn seeded_deals_repeat() {
+    let mut first = StdRng::seed_from_u64(42);
+    let mut second = StdRng::seed_from_u64(42);
+    let a = deal(&mut first, 4, false, Rules::default());
+    let b = deal(&mut second, 4, false, Rules::default());
+    assert_eq!(format!("{a:?}"), format!("{b:?}"));
+}
+
+#[test]
+fn rewinding_replays_the_game() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let transfer = Presets::default()
+        .get("Transfer")
+        .expect("a built-in preset");
+    let mut engine = deal(&mut rng, 3, false, transfer);
+    let mut states = Vec::new();
+    play_out(&mut engine, |engine| {
+        states.push(format!("{engine:?}"));
+        random_move(engine, &mut rng)
+    });
+    for (moves, state) in states.iter().enumerate() {
+        let rewound = engine.rewind(moves).expect("rewind");
+        assert_eq!(&format!("{rewound:?}"), state, "after {moves} moves");
+    }
+}
+
+#[test]
+fn saved_games_play_back() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut engine = deal(&mut rng, 4, true, Rules::default());
+    engine.set_leader(2);
+    for _ in 0..20 {
+        let (player, action) = random_move(&engine, &mut rng);
+        engine.apply(player, action).expect("a legal move");
+    }
+    let names = ["A", "B", "C", "D"].map(String::from).to_vec();
+    let replay = Replay::record(names, &engine, DeckSize::Standard, 1);
+    let text = serde_json::to_string(&replay).expect("a saved game");
+    let loaded: Replay = serde_json::from_str(&text).expect("a saved game");
+    assert_eq!(loaded, replay);
+    let game = loaded.game().expect("a game to play back");
+    assert_eq!(format!("{game:?}"), format!("{engine:?}"));
+
+    let mut broken = loaded;
+    broken.log.swap(0, 1);
+    assert!(matches!(broken.game(), Err(DurakError::State(_))));
+}
+
+fn card(rank: Rank, suit: Suit) -> CardId {
+    CardId {
+        suit,
+        rank,
+        deck_id: 0,
+    }
+}
+
+/// Two players dealt `first` and `second`, the rest of a standard deck left
+/// in the talon under `trump`.
+fn scripted(first: [CardId; 6], second: [CardId; 6], trump: CardId, rules: Rules) -> Engine {
+    let dealt: Vec<CardId> = first
+        .iter()
+        .zip(&second)
+        .flat_map(|(first, second)| [*first, *second])
+        .collect();
+    let mut rest: Vec<CardId> = rules::card_ids(DeckSize::Standard, 1)
+        .into_iter()
+        .filter(|card| *card != trump && !dealt.contains(card))
+        .collect();
+    rest.reverse();
+    let talon: Vec<CardId> = [trump]
+        .into_iter()
+        .chain(rest)
+        .chain(dealt.into_iter().rev())
+        .collect();
+    let (engine, _) = Engine::new(talon, 2, None, rules).expect("a valid deal");
+    engine
+}
+
+#[test]
+fn scripted_bout() {
+    use Rank::*;
+    use Suit::*;
+    let mut engine = scripted(
+        [
+            card(Six, Spades),
+            card(Seven, Hearts),
+            card(Seven, Clubs),
+            card(Ten, Diamonds),
+            card(Queen, Hearts),
+            card(King, Clubs),
+        ],
+        [
+            card(Eight, Hearts),
+            card(Nine, Clubs),
+            card(Jack, Diamonds),
+            card(Ace, Diamonds),
+            card(Six, Hearts),
+            card(Queen, Diamonds),
+        ],
+        card(Ten, Spades),
+        Rules::default(),
+    );
+    // The six of trumps is the lowest trump, so the first player leads.
+    assert_eq!((engine.attacker(), engine.defender()), (0, 1));
+
+    engine
+        .apply(0, Action::Attack(card(Seven, Hearts)))
+        .unwrap();
+    assert!(matches!(
+        engine.apply(1, Action::Defend(0, card(Six, Hearts))),
+        Err(DurakError::Rules(_))
+    ));
+    assert!(matches!(
+        engine.apply(1, Action::Defend(0, card(Nine, Clubs))),
+        Err(DurakError::Rules(_))
+    ));
+    engine
+        .apply(1, Action::Defend(0, card(Eight, Hearts)))
+        .unwrap();
+    assert!(matches!(
+        engine.apply(0, Action::Attack(card(Ten, Diamonds))),
+        Err(DurakError::Rules(_))
+    ));
+    engine.apply(0, Action::Attack(card(Seven, Clubs))).unwrap();
+    engine
+        .apply(1, Action::Defend(1, card(Nine, Clubs)))
+        .unwrap();
+
+    // Nothing left to throw in, so the bout is beaten off: the table is
+    // discarded, both draw back up to six and the defender leads next.
+    assert_eq!(engine.discard().len(), 4);
+    assert!(engine.table().is_empty());
+    assert_eq!(engine.hand(0).len(), 6);
+    assert_eq!(engine.hand(1).len(), 6);
+    assert_eq!((engine.attacker(), engine.defender()), (1, 0));
+    check_invariants(&engine);
+
+    engine
+        .apply(1, Action::Attack(card(Jack, Diamonds)))
+        .unwrap();
+    engine.apply(0, Action::Take).unwrap();
+    // The attacker still holds a jack to throw in, so the bout waits for them.
+    assert_eq!(engine.table().len(), 1);
+    assert_eq!(engine.hand(0).len(), 6);
+    engine.apply(1, Action::Pass).unwrap();
+    assert!(engine.table().is_empty());
+    assert_eq!(engine.hand(0).len(), 7);
+    assert_eq!((engine.attacker(), engine.defender()), (1, 0));
+    check_invariants(&engine);
+}
+
+#[test]
+fn scripted_transfers() {
+    use Rank::*;
+    use Suit::*;
+    let first = [
+        card(Six, Spades),
+        card(Seven, Hearts),
+        card(Eight, Clubs),
+        card(Ten, Diamonds),
+        card(Queen, Hearts),
+        card(King, Clubs),
+    ];
+    let second = [
+        card(Seven, Spades),
+        card(Seven, Diamonds),
+        card(Jack, Diamonds),
+        card(Ace, Diamonds),
+        card(Six, Hearts),
+        card(Queen, Diamonds),
+    ];
+
+    let mut classic = scripted(first, second, card(Ten, Spades), Rules::default());
+    classic
+        .apply(0, Action::Attack(card(Seven, Hearts)))
+        .unwrap();
+    assert!(matches!(
+        classic.apply(1, Action::Transfer(card(Seven, Diamonds))),
+        Err(DurakError::Rules(_))
+    ));
+
+    let rules = Rules {
+        transfers: true,
+        show_trump: true,
+        ..Rules::default()
+    };
+    let mut engine = scripted(first, second, card(Ten, Spades), rules);
+    engine
+        .apply(0, Action::Attack(card(Seven, Hearts)))
+        .unwrap();
+    // Only a trump can be shown.
+    assert!(matches!(
+        engine.apply(1, Action::ShowTrump(card(Seven, Diamonds))),
+        Err(DurakError::Rules(_))
+    ));
+    engine
+        .apply(1, Action::ShowTrump(card(Seven, Spades)))
+        .unwrap();
+    assert_eq!((engine.attacker(), engine.defender()), (1, 0));
+    assert_eq!(engine.hand(1).len(), 6, "a shown trump stays in the hand");
+    assert!(matches!(
+        engine.apply(0, Action::Transfer(card(Eight, Clubs))),
+        Err(DurakError::Rules(_))
+    ));
+    engine
+        .apply(0, Action::Defend(0, card(Queen, Hearts)))
+        .unwrap();
+    check_invariants(&engine);
+}
+
+#[test]
+fn draws_can_be_ruled_out() {
+    for draws in [true, false] {
+        let rules = Rules {
+            draws,
+            ..Rules::default()
+        };
+        for seed in 0..300 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut engine = deal(&mut rng, 2, false, rules);
+            let outcome = play_out(&mut engine, |engine| random_move(engine, &mut rng));
+            if !draws {
+                assert_ne!(outcome, Outcome::Draw);
+            }
+        }
+    }
+}
+
+#[test]
+fn scripted_team_game() {
+    use Rank::*;
+    use Suit::*;
+    // One card each, dealt from the end of the talon; the last player gets
+    // the face up trump.
+    let talon = vec![
+        card(Eight, Hearts),
+        card(Ten, Hearts),
+        card(Seven, Hearts),
+        card(Six, Hearts),
+    ];
+    let rules = Rules {
+        deal_size: 1,
+        ..Rules::default()
+    };
+    let teams = Some(vec![0, 1, 0, 1]);
+    let (mut engine, _) = Engine::new(talon, 4, teams, rules).expect("a valid deal");
+    assert_eq!((engine.attacker(), engine.defender()), (0, 1));
+
+    engine.apply(0, Action::Attack(card(Six, Hearts))).unwrap();
+    engine
+        .apply(1, Action::Defend(0, card(Seven, Hearts)))
+        .unwrap();
+    assert_eq!(engine.outcome(), None);
+    assert_eq!((engine.attacker(), engine.defender()), (2, 3));
+    check_invariants(&engine);
+
+    engine.apply(2, Action::Attack(card(Ten, Hearts))).unwrap();
+    engine.apply(3, Action::Take).unwrap();
+    // Both players of the first team are out, the second one is left
+    // holding cards.
+    assert_eq!(engine.outcome(), Some(Outcome::LosingTeam(1)));
+    check_invariants(&engine);
+}
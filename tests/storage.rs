@@ -0,0 +1,28 @@
+#![cfg(feature = "gui")]
+
+use durak_rust::storage::Assets;
+use ggez::audio::SoundData;
+
+#[test]
+fn sounds_are_found_by_name() {
+    let mut assets = Assets::new();
+    let click = assets.insert("/click.ogg", SoundData::from_bytes(b"click"));
+    let shuffle = assets.insert("/shuffle.ogg", SoundData::from_bytes(b"shuffle"));
+    assert_ne!(click, shuffle);
+    assert_eq!(assets.find::<SoundData>("/click.ogg"), Some(click));
+    assert_eq!(assets.find::<SoundData>("/deal.ogg"), None);
+    assert!(assets.get(click).is_some());
+
+    // Loading a sound again under the same name replaces it in place.
+    let again = assets.insert("/click.ogg", SoundData::from_bytes(b"clack"));
+    assert_eq!(again, click);
+}
+
+#[test]
+fn sound_handles_belong_to_their_registry() {
+    let mut assets = Assets::new();
+    let click = assets.insert("/click.ogg", SoundData::from_bytes(b"click"));
+    let mut other = Assets::new();
+    other.insert("/click.ogg", SoundData::from_bytes(b"click"));
+    assert!(other.get(click).is_none());
+}